@@ -1,8 +1,8 @@
 use clap::{App, Arg};
 use std::collections::HashMap;
 
-/// An enumeration over the style of output desired.
 arg_enum! {
+    /// An enumeration over the style of output desired.
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
     pub enum Style {
         Json,
@@ -29,7 +29,6 @@ pub fn cli() -> App<'static, 'static> {
         .arg(
             flag("lint")
                 .short("l")
-                .required(true)
                 .takes_value(true)
                 .number_of_values(1)
                 .multiple(true)
@@ -42,7 +41,50 @@ pub fn cli() -> App<'static, 'static> {
                 .possible_values(&["json", "line", "verbose"]),
         )
         .arg(flag("split").short("s").takes_value(true))
+        .arg(
+            flag("split-overlap")
+                .takes_value(true)
+                .value_name("LINES"),
+        )
+        .arg(
+            flag("format")
+                .short("f")
+                .takes_value(true)
+                .possible_values(&[
+                    "text", "markdown", "rst", "asciidoc", "html", "xml", "latex", "rust",
+                    "python", "go", "javascript", "c", "shell",
+                ]),
+        )
+        .arg(
+            flag("fail-on")
+                .takes_value(true)
+                .value_name("SEVERITY")
+                .possible_values(&["info", "suggestion", "warning", "error"]),
+        )
+        .arg(
+            flag("min-severity")
+                .takes_value(true)
+                .value_name("SEVERITY")
+                .possible_values(&["info", "suggestion", "warning", "error"]),
+        )
         .arg(flag("no-unicode"))
+        .arg(flag("report-unused-suppressions"))
+        .arg(flag("fix").conflicts_with("fix-dry-run"))
+        .arg(flag("fix-dry-run"))
+        .arg(flag("metrics"))
+        .arg(
+            flag("config")
+                .short("c")
+                .takes_value(true)
+                .value_name("PATH")
+                .conflicts_with("no-config"),
+        )
+        .arg(flag("no-config"))
+        .arg(
+            flag("stdin-filename")
+                .takes_value(true)
+                .value_name("PATH"),
+        )
 }
 
 struct Doc {
@@ -71,7 +113,8 @@ lazy_static! {
             "The file(s) to run the lints on to check for errors. PATTERN is a \
              glob matching all the files which should be checked. Recursive \
              searches can be done by using glob syntax for recursion. If no \
-             file is specified, temper will read from stdin.");
+             file is specified, the `files` of the config file are checked, \
+             and if there are none, temper will read from stdin.");
 
         doc!(us, "lint",
             "The lintset(s) to use to check files.",
@@ -83,14 +126,18 @@ lazy_static! {
              the single argument will turn into multiple arguments. Because \
              the lint flag will only take one value (multiple lintsets are passed \
              via repeated use of the flag), the extra arguments will become \
-             file arguments rather than lintset arguments.");
+             file arguments rather than lintset arguments. \
+             \n\nIf this flag isn't given, the `lints` of the config file are \
+             used instead.");
 
         doc!(us, "output",
             "The style in which to print the results.",
             "The style in which to print the results. \
-             \n\n`json` will output the results of the lint in json format. \
-             This option mostly exists for interoperability with outside \
-             programs. \
+             \n\n`json` will output the results of the lint in json format, \
+             with one object per match on each line. Each object holds the \
+             file, line, column, byte offsets, lint name, severity, message \
+             and matched text of the match. This option mostly exists for \
+             interoperability with outside programs. \
              \n\n`line` will output the results of the lint in line format, \
              with each suggestion taking up one line (given a sufficiently \
              wide terminal window). In this output mode, the line and column \
@@ -102,25 +149,139 @@ lazy_static! {
              multiple lines, and the offending line included in the output, \
              with the match emphasized in the terminal output.");
 
+        doc!(us, "format",
+            "The markup language of the files being checked.",
+            "The markup language of the files being checked, which decides \
+             which parts of them are linted. By default, this is guessed from \
+             the extension of each file (or of --stdin-filename). \
+             \n\n`text` lints the whole file. \
+             \n\n`markdown` only lints prose, skipping front matter, code \
+             blocks, code spans, html, link targets and urls. This is the \
+             default for `.md` and `.markdown` files. \
+             \n\n`rst` only lints the prose of reStructuredText and Sphinx \
+             sources: paragraphs, titles, list items and the bodies of \
+             admonitions like `.. note::`. Other directives, comments, literal \
+             blocks, tables, roles and link targets are skipped. This is the \
+             default for `.rst` and `.rest` files. \
+             \n\n`asciidoc` only lints the prose of AsciiDoc documents: \
+             paragraphs, titles, list items and admonitions. Attribute \
+             entries, comments, listing, literal and passthrough blocks, \
+             tables, and the targets of macros are skipped. This is the \
+             default for `.adoc` and `.asciidoc` files. \
+             \n\n`html` and `xml` only lint text nodes, and the values of the \
+             `alt` and `title` attributes. Tags, comments, entities and the \
+             content of elements like `script`, `style`, `pre` and `code` \
+             are skipped. More can be added with the `skip` and \
+             `attributes` lists of an `[html]` table in the config file. \
+             These are the defaults for `.html`, `.htm` and `.xhtml` files, \
+             and for `.xml` and `.dbk` files. \
+             \n\n`latex` only lints the text of LaTeX documents and the \
+             arguments of commands like `\\section` and `\\emph`. The \
+             preamble, comments, math, verbatim environments and the \
+             arguments of commands like `\\ref`, `\\label` and `\\cite` are \
+             skipped. This is the default for `.tex` and `.ltx` files. \
+             \n\n`rust`, `python`, `go`, `javascript`, `c` and `shell` only \
+             lint the comments of source code, including doc comments and \
+             Python docstrings. Code, other strings, shell here-documents, \
+             code spans and examples inside of comments are skipped. \
+             `javascript` also covers TypeScript, and `c` covers C++. These \
+             are the defaults for files with the extensions of those \
+             languages, like `.rs`, `.py`, `.go`, `.ts`, `.cpp` and `.sh`.");
+
         doc!(us, "split",
             "The number of partitions that should be made in each file when \
-             searching. Can drastically improve performance at the cost of \
-             correctness for patterns which match across lines.",
+             searching. Can drastically improve performance.",
             "The number of partitions that should be made in each file when \
              searching. Searching smaller buffers in parallel is much faster \
              and is easier to run in parallel, so increasing this number can \
              result in drastically improved performance. \
-             \n\nHowever, there are a number of things to note with this \
-             option. First, increasing this number ad infinitum will not \
+             \n\nHowever, increasing this number ad infinitum will not \
              necessarily yield improved performance, since each added split \
-             will require each regular expression to be recompiled for that \
-             file, which has a performance cost. The best value for this setting \
+             has to be scheduled and searched separately, which has a \
+             performance cost. The best value for this setting \
              will be dependent on your files and your computer (specifically \
              the number of logical cores on your CPU), so some trial and error \
-             may be necessary to obtain the best possible performance. In \
-             addition, this option partitions the file by line. If a match \
-             happens to lie on two lines that will be separated after the \
-             partition, it will no longer match, yielding incorrect results.");
+             may be necessary to obtain the best possible performance. \
+             \n\nSplitting never changes the results: each partition is \
+             searched along with the lines after it that a match could reach \
+             (see --split-overlap), and duplicate matches are removed.");
+
+        doc!(us, "split-overlap",
+            "The number of lines partitions overlap by for unbounded patterns.",
+            "The number of lines that each partition made by --split is \
+             extended by when some lint can match an unbounded number of line \
+             breaks (for example, with `\\s+`). When every lint has a limit, \
+             partitions are extended by exactly as many lines as a match can \
+             span, and this option has no effect. Defaults to 10.");
+
+        doc!(us, "fail-on",
+            "Exit with a non-zero status if any match is at least this severe.",
+            "Exit with a non-zero status if any match is at least as severe as \
+             SEVERITY. This is useful for failing CI builds on lint errors. \
+             \n\nAn exit status of 1 means that temper ran successfully, but \
+             found matches at or above SEVERITY. An exit status of 2 means \
+             that temper itself failed, for example because a lintset or \
+             file could not be read. Without this option, temper exits with \
+             0 whenever linting completes.");
+
+        doc!(us, "min-severity",
+            "Only report matches which are at least this severe.",
+            "Only report matches which are at least as severe as SEVERITY. \
+             Less severe matches are neither printed nor counted.");
+
+        doc!(us, "config",
+            "The config file to use.",
+            "The config file to use instead of searching for one. By default, \
+             temper looks for a `.temper.toml` file in the current directory \
+             and each of its parents, and uses the first one it finds. \
+             \n\nThe config file can set `lints`, `files` and `exclude` (lists \
+             of globs, relative to the config file), `output`, `split`, \
+             `split_overlap`, \
+             `unicode`, `min_severity` and `fail_on`, which act as defaults \
+             for the matching flags. A `[severity]` table overrides the \
+             severity of lints by name, for example \
+             `\"temper.cliches\" = \"error\"`, and an `[html]` table adds \
+             to the `skip`ped elements and linted `attributes` of HTML and \
+             XML files.");
+
+        doc!(us, "no-config",
+            "Don't read any config file.");
+
+        doc!(us, "report-unused-suppressions",
+            "Report suppression directives which don't suppress anything.",
+            "Report suppression directives which don't suppress anything. \
+             \n\nMatches can be suppressed with directives in the text itself, \
+             usually written inside of comments: \
+             `temper-disable-next-line [LINT...]` suppresses the line after \
+             it, `temper-disable-line [LINT...]` suppresses its own line, and \
+             `temper-disable [LINT...]` suppresses everything up to a \
+             matching `temper-enable [LINT...]`. Without any lint names, \
+             every lint is suppressed. \
+             \n\nWith this flag, directives that no longer suppress any \
+             matches are reported as `temper.unused-suppression` warnings.");
+
+        doc!(us, "fix",
+            "Rewrite files in place with the suggested replacements.",
+            "Rewrite files in place with the suggested replacements, such as \
+             the values of mapping lints. The case of the original text is \
+             kept, so `Utilize` is replaced by `Use`. When fixes overlap, only \
+             the one starting first is applied. Matches that were fixed are \
+             not reported. Text read from stdin can't be fixed in place.");
+
+        doc!(us, "fix-dry-run",
+            "Print the fixes that --fix would make as a unified diff.",
+            "Print the fixes that --fix would make as a unified diff. Since \
+             nothing is written, every match is still reported and counts \
+             towards --fail-on.");
+
+        doc!(us, "metrics",
+            "Print the readability scores of each file.",
+            "Print the readability scores of the prose in each file after its \
+             matches: Flesch reading ease, Flesch-Kincaid grade, Gunning fog, \
+             Coleman-Liau, SMOG and ARI. Only the prose regions of a file are \
+             scored, so code blocks and the like don't count. \
+             \n\nTo fail on a score instead, use a lint of kind `metric` with \
+             a `metric` and a `max` or `min`.");
 
         doc!(us, "no-unicode",
             "Disable unicode support for wildcard characters.",
@@ -128,6 +289,13 @@ lazy_static! {
              Can improve performance at the cost of correctness (especially \
              for non-English texts).");
 
+        doc!(us, "stdin-filename",
+            "The name to report for text read from stdin.",
+            "The name to report for text read from stdin. PATH is only used \
+             for display and to decide how the text should be treated (for \
+             example, by its extension); nothing is read from it. Defaults to \
+             `<stdin>`.");
+
         us
     };
 }
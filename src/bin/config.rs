@@ -0,0 +1,163 @@
+//! Module `config.rs` finds and reads `.temper.toml` project configuration
+//! files.
+
+use failure::Error;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml;
+
+use temper::lint::Severity;
+use temper::markup::html;
+
+pub const CONFIG_NAME: &str = ".temper.toml";
+
+/// Project-wide defaults, which are overridden by any flags passed on the
+/// command line.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Globs of lintsets to use.
+    pub lints: Vec<String>,
+    /// Globs of files to check when none are given on the command line.
+    pub files: Vec<String>,
+    /// Globs of files which should never be checked.
+    pub exclude: Vec<String>,
+    pub output: Option<String>,
+    pub split: Option<usize>,
+    pub split_overlap: Option<usize>,
+    pub unicode: Option<bool>,
+    pub min_severity: Option<Severity>,
+    pub fail_on: Option<Severity>,
+    /// Overrides of the severity of individual lints, keyed by lint name.
+    pub severity: HashMap<String, Severity>,
+    /// Which elements and attributes of HTML and XML files are linted.
+    pub html: html::Options,
+}
+
+impl Config {
+    /// Looks for a config file in `dir` and each of its parents, returning the
+    /// first one found.
+    pub fn discover<P: AsRef<Path>>(dir: P) -> Result<Option<Config>, Error> {
+        for d in dir.as_ref().ancestors() {
+            let path = d.join(CONFIG_NAME);
+            if path.is_file() {
+                return Config::load(path).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Reads the config file at `path`. Relative globs are resolved against
+    /// the directory the file is in, so that the config applies the same way
+    /// regardless of where temper is run from.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Config, Error> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+        let mut config: Config = toml::from_str(&contents)
+            .map_err(|e| format_err!("{}: {}", path.display(), e))?;
+
+        let dir = absolute(path.parent().unwrap_or_else(|| Path::new(".")))?;
+        for pattern in config
+            .lints
+            .iter_mut()
+            .chain(config.files.iter_mut())
+            .chain(config.exclude.iter_mut())
+        {
+            *pattern = dir.join(&pattern).to_string_lossy().into_owned();
+        }
+
+        Ok(config)
+    }
+}
+
+/// Makes `path` absolute by joining it onto the current directory, dropping any
+/// `.` components along the way.
+pub fn absolute<P: AsRef<Path>>(path: P) -> Result<PathBuf, Error> {
+    Ok(env::current_dir()?.join(path).components().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    /// Creates an empty directory for a test to write config files into.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("temper-config-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn config_discover_parents() {
+        let dir = scratch("discover");
+        let nested = dir.join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(dir.join(CONFIG_NAME), "split = 4\n").unwrap();
+
+        let config = Config::discover(&nested).unwrap().unwrap();
+        assert_eq!(Some(4), config.split);
+
+        // The closest config wins
+        fs::write(nested.join(CONFIG_NAME), "split = 2\n").unwrap();
+        let config = Config::discover(&nested).unwrap().unwrap();
+        assert_eq!(Some(2), config.split);
+    }
+
+    #[test]
+    fn config_load_relative_globs() {
+        let dir = scratch("globs");
+        let path = dir.join(CONFIG_NAME);
+        fs::write(
+            &path,
+            "lints = ['lints/*.toml']\nfiles = ['docs/**/*.md']\nexclude = ['/abs/x.md']\n",
+        ).unwrap();
+
+        let config = Config::load(&path).unwrap();
+        let dir = absolute(&dir).unwrap();
+        assert_eq!(
+            vec![dir.join("lints/*.toml").to_string_lossy().into_owned()],
+            config.lints
+        );
+        assert_eq!(
+            vec![dir.join("docs/**/*.md").to_string_lossy().into_owned()],
+            config.files
+        );
+        assert_eq!(vec![String::from("/abs/x.md")], config.exclude);
+    }
+
+    #[test]
+    fn config_unknown_fields() {
+        let dir = scratch("unknown");
+        let path = dir.join(CONFIG_NAME);
+
+        fs::write(&path, "splitt = 4\n").unwrap();
+        let err = Config::load(&path).unwrap_err().to_string();
+        assert!(err.contains("splitt"), "{}", err);
+
+        fs::write(&path, "[html]\nskipp = ['aside']\n").unwrap();
+        assert!(Config::load(&path).is_err());
+    }
+
+    #[test]
+    fn config_html_extends_defaults() {
+        let dir = scratch("html");
+        let path = dir.join(CONFIG_NAME);
+        fs::write(&path, "[html]\nskip = ['aside', 'code']\nattributes = ['aria-label']\n")
+            .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        let defaults = html::Options::default();
+        assert_eq!(defaults.skip.len() + 1, config.html.skip.len());
+        assert!(config.html.skip.starts_with(&defaults.skip));
+        assert_eq!(Some(&String::from("aside")), config.html.skip.last());
+        assert_eq!(vec!["alt", "title", "aria-label"], config.html.attributes);
+
+        fs::write(&path, "split = 2\n").unwrap();
+        assert_eq!(defaults, Config::load(&path).unwrap().html);
+    }
+}
@@ -6,6 +6,7 @@ extern crate temper;
 extern crate bytecount;
 #[macro_use]
 extern crate clap;
+#[macro_use]
 extern crate failure;
 extern crate glob;
 #[macro_use]
@@ -13,134 +14,277 @@ extern crate lazy_static;
 extern crate memchr;
 extern crate memmap;
 extern crate rayon;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
 extern crate termcolor;
+extern crate toml;
 
 mod opt;
 mod cli;
+mod config;
 mod printer;
 
 use failure::Error;
-use glob::glob;
+use glob::{glob, Pattern};
 use memmap::Mmap;
 use rayon::prelude::*;
 use std::cmp;
-use std::fs::File;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
 use std::result::Result;
 use std::str;
 use std::sync::Arc;
 use termcolor::{BufferWriter, ColorChoice};
 
+use cli::Style;
+use config::absolute;
 use opt::*;
 use printer::*;
+use temper::fix;
 use temper::lint::*;
+use temper::linter::*;
+use temper::markup::Format;
 use temper::prose::*;
 
 const EOL: u8 = b'\n';
 
-// TODO: multi-line compatibility
-fn get_line(clens: &[usize], linum: usize) -> (usize, usize) {
-    (clens[linum - 1], clens[linum])
+/// The exit status used when matches at or above `--fail-on` were found.
+const EXIT_FINDINGS: i32 = 1;
+/// The exit status used when temper itself failed.
+const EXIT_FAILURE: i32 = 2;
+
+/// A summary of the matches found in one or more files.
+#[derive(Clone, Copy, Debug, Default)]
+struct Tally {
+    count: usize,
+    worst: Option<Severity>,
+    fixes: usize,
 }
 
-fn go(opt: Opt) -> Result<usize, Error> {
-    // TODO: stdin
+impl Tally {
+    fn merge(self, other: Tally) -> Tally {
+        Tally {
+            count: self.count + other.count,
+            worst: cmp::max(self.worst, other.worst),
+            fixes: self.fixes + other.fixes,
+        }
+    }
+}
+
+/// Finds the byte range of the lines from `linum` up to the one containing the
+/// last byte of a match ending at `end`.
+fn get_lines(clens: &[usize], linum: usize, end: usize) -> (usize, usize) {
+    let last = cmp::max(clens.partition_point(|&c| c < end), linum);
+    (clens[linum - 1], clens[cmp::min(last, clens.len() - 1)])
+}
+
+fn go(opt: &Opt) -> Result<Tally, Error> {
     let mut ls = Vec::new();
     let mut fs = Vec::new();
 
-    let split = cmp::max(opt.split, 1);
-    let style = opt.style;
-    let unicode = opt.unicode;
-
     // A note on the unquoted glob:
     // When the os expands an unquoted glob, it'll turn into multiple values
     // The -l flag only takes one value per -l, so the rest become arguments
     // as files
-    for l in opt.lints {
-        for entry in glob(&l)? {
+    for l in &opt.lints {
+        let before = ls.len();
+        for entry in glob(l)? {
             ls.push(entry?);
         }
+        if ls.len() == before {
+            bail!("no lints found matching `{}`", l);
+        }
     }
 
-    for f in opt.files {
-        for entry in glob(&f)? {
-            fs.push(entry?);
+    let exclude = opt.exclude
+        .iter()
+        .map(|p| Pattern::new(p))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for f in &opt.files {
+        let mut entries = glob(f)?.peekable();
+        if entries.peek().is_none() {
+            bail!("no files found matching `{}`", f);
+        }
+        for entry in entries {
+            let entry = entry?;
+            let path = absolute(&entry)?;
+            if !exclude.iter().any(|p| p.matches_path(&path)) {
+                fs.push(entry);
+            }
         }
     }
 
-    let lints: Lintset = linters(ls.iter().map(PathBuf::from).collect())?;
+    let mut lints: Lintset = linters(ls.iter().map(PathBuf::from).collect())?;
+    for lint in &mut lints {
+        if let Some(&severity) = opt.severities.get(&lint.name) {
+            lint.severity = severity;
+        }
+    }
+    let linter = Linter::new(lints, opt.unicode)?;
     let files: Vec<PathBuf> = fs.iter().map(PathBuf::from).collect();
 
     let bufwtr = Arc::new(BufferWriter::stdout(ColorChoice::Always));
 
+    if opt.files.is_empty() {
+        if opt.fix {
+            bail!("stdin can't be fixed in place; use --fix-dry-run to see the fixes");
+        }
+
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+
+        let name = &opt.stdin_filename;
+        return check(opt, name, &text, &linter, &bufwtr).map(|(t, _)| t);
+    }
+
     files
         .par_iter()
-        .map(|file| -> Result<usize, Error> {
-            let f = File::open(file)?;
-            let mmap = unsafe { Mmap::map(&f)? };
-            let mmap = str::from_utf8(&mmap)?;
-
-            let bufwtr = Arc::clone(&bufwtr);
-            let mut buffer = bufwtr.buffer();
-            let prose = Prose {
-                name: file.file_name().unwrap().to_str().unwrap(),
-                text: mmap,
-                split: split,
-                unicode: unicode,
-                eol: EOL,
+        .map(|file| -> Result<Tally, Error> {
+            let (tally, fixed) = {
+                let f = File::open(file)?;
+                let mmap = unsafe { Mmap::map(&f)? };
+                let mmap = str::from_utf8(&mmap)?;
+
+                check(opt, &file.to_string_lossy(), mmap, &linter, &bufwtr)?
             };
-            let line_lengths = prose.line_lengths();
-            let matches = prose.lint(&lints)?;
-            let mut match_count = 0;
-
-            {
-                // TODO: Actually use terminal's width
-                let mut printer = Printer {
-                    wtr: &mut buffer,
-                    style: style,
-                    colors: Colors::default(),
-                    eol: EOL,
-                };
-
-                for m in matches {
-                    let (ls, le) = get_line(&line_lengths, m.line);
-                    let line = &mmap[ls..le].trim_right();
-                    let o = Offset {
-                        start: m.offset.start - ls,
-                        end: m.offset.end - ls,
-                    };
-                    printer.write_match(&m, line, o)?;
-                    match_count += 1;
-                }
-            }
 
-            bufwtr.print(&buffer)?;
+            // The file is only written once it's no longer mapped
+            if let Some(fixed) = fixed {
+                fs::write(file, fixed)?;
+            }
 
-            Ok(match_count)
+            Ok(tally)
         })
         .reduce(
-            || Ok(0),
+            || Ok(Tally::default()),
             |a, b| match (a, b) {
-                (Ok(a), Ok(b)) => Ok(a + b),
+                (Ok(a), Ok(b)) => Ok(a.merge(b)),
                 (Err(a), _) => Err(a),
                 (_, Err(b)) => Err(b),
             },
         )
 }
 
+/// Lints a single piece of text, printing its matches and returning a summary
+/// of them. When fixing, the fixed text is also returned if it changed.
+fn check(
+    opt: &Opt,
+    name: &str,
+    text: &str,
+    linter: &Linter,
+    bufwtr: &Arc<BufferWriter>,
+) -> Result<(Tally, Option<String>), Error> {
+    let mut buffer = bufwtr.buffer();
+    let prose = Prose {
+        name,
+        text,
+        split: cmp::max(opt.split, 1),
+        overlap: opt.split_overlap,
+        eol: EOL,
+        format: opt.format.unwrap_or_else(|| Format::from_path(name)),
+        unused_suppressions: opt.unused_suppressions,
+        markup: &opt.markup,
+    };
+    let line_lengths = prose.line_lengths();
+    let matches: Vec<Match> = prose
+        .lint(linter)?
+        .into_iter()
+        .filter(|m| opt.min_severity.is_none_or(|min| m.severity >= min))
+        .collect();
+    let mut tally = Tally::default();
+    let mut fixed = None;
+
+    // Matches which were fixed in place aren't reported
+    let mut applied = Vec::new();
+    if opt.fix || opt.fix_dry_run {
+        let f = fix::apply(text, &matches);
+        tally.fixes = f.applied.len();
+
+        if opt.fix_dry_run {
+            buffer.write_all(fix::diff(name, text, &f).as_bytes())?;
+        } else {
+            if tally.fixes > 0 {
+                fixed = Some(f.text);
+            }
+            applied = f.applied;
+        }
+    }
+
+    {
+        // TODO: Actually use terminal's width
+        let mut printer = Printer {
+            wtr: &mut buffer,
+            style: opt.style,
+            eol: EOL,
+        };
+
+        for (i, m) in matches.iter().enumerate() {
+            if applied.binary_search(&i).is_ok() {
+                continue;
+            }
+
+            let (ls, le) = get_lines(&line_lengths, m.line, m.offset.end);
+            let line = &text[ls..le].trim_end();
+            let o = Offset {
+                start: m.offset.start - ls,
+                end: m.offset.end - ls,
+            };
+            printer.write_match(m, line, o)?;
+            tally.count += 1;
+            tally.worst = cmp::max(tally.worst, Some(m.severity));
+        }
+
+        if opt.metrics {
+            printer.write_metrics(name, &prose.stats())?;
+        }
+    }
+
+    bufwtr.print(&buffer)?;
+
+    Ok((tally, fixed))
+}
+
 fn main() {
     let opt = Opt::parse().unwrap_or_else(|e| {
-        eprintln!("{}", e);
-        std::process::exit(1);
+        let code = match e.downcast_ref::<clap::Error>() {
+            Some(e) if !e.use_stderr() => {
+                println!("{}", e);
+                0
+            }
+            _ => {
+                eprintln!("{}", e);
+                EXIT_FAILURE
+            }
+        };
+        std::process::exit(code);
     });
 
-    match go(opt) {
-        Ok(c) => {
-            println!("{} suggestions found.", c);
+    match go(&opt) {
+        Ok(t) => {
+            // Keep json output machine-readable
+            if opt.style != Style::Json {
+                println!("{} suggestions found.", t.count);
+                if opt.fix {
+                    println!("{} fixes applied.", t.fixes);
+                } else if opt.fix_dry_run {
+                    println!("{} fixes available.", t.fixes);
+                }
+            }
+
+            if let Some(threshold) = opt.fail_on {
+                if t.worst >= Some(threshold) {
+                    std::process::exit(EXIT_FINDINGS);
+                }
+            }
         }
         Err(e) => {
-            eprintln!("error: {} {}", e.cause(), e.backtrace());
-            std::process::exit(1);
+            eprintln!("error: {} {}", e.as_fail(), e.backtrace());
+            std::process::exit(EXIT_FAILURE);
         }
     }
 }
@@ -1,6 +1,13 @@
+use clap::ArgMatches;
 use failure::Error;
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
 
 use cli::*;
+use config::*;
+use temper::lint::Severity;
+use temper::markup::{self, Format};
 
 // TODO: field for style
 #[derive(Clone, Debug)]
@@ -9,27 +16,99 @@ pub struct Opt {
     pub style: Style,
     // Note: # of cores / # of files is a good choice
     pub split: usize,
+    pub split_overlap: usize,
+    // If none, the format of each file is guessed from its name
+    pub format: Option<Format>,
     pub unicode: bool,
-    // TODO: If 0 args, read from stdin
+    pub unused_suppressions: bool,
+    pub fix: bool,
+    pub fix_dry_run: bool,
+    pub metrics: bool,
+    pub fail_on: Option<Severity>,
+    pub min_severity: Option<Severity>,
+    pub severities: HashMap<String, Severity>,
+    // If empty, prose is read from stdin
     pub files: Vec<String>,
+    pub exclude: Vec<String>,
+    pub stdin_filename: String,
+    pub markup: markup::Options,
 }
 
 impl Opt {
     pub fn parse() -> Result<Opt, Error> {
         let ms = cli().get_matches_safe()?;
 
-        let lints = values_t!(ms, "lint", String)?;
-        let style = value_t!(ms, "output", Style).unwrap_or(Style::Line);
-        let split = value_t!(ms, "split", usize).unwrap_or(1);
-        let files = values_t!(ms, "file", String)?;
-        let unicode = !ms.is_present("no-unicode");
+        let config = if ms.is_present("no-config") {
+            Config::default()
+        } else if let Some(path) = ms.value_of("config") {
+            Config::load(path)?
+        } else {
+            Config::discover(env::current_dir()?)?.unwrap_or_default()
+        };
+
+        // Flags given on the command line always win over the config
+        let lints = values_t!(ms, "lint", String).unwrap_or(config.lints);
+        if lints.is_empty() {
+            bail!(
+                "no lintsets given; pass --lint or set `lints` in {}",
+                CONFIG_NAME
+            );
+        }
+
+        let style = match (value::<Style>(&ms, "output")?, config.output) {
+            (Some(style), _) => style,
+            (None, Some(output)) => output
+                .parse::<Style>()
+                .map_err(|e| format_err!("{}: {}", CONFIG_NAME, e))?,
+            (None, None) => Style::Line,
+        };
+        let split = value(&ms, "split")?.or(config.split).unwrap_or(1);
+        let split_overlap = value(&ms, "split-overlap")?
+            .or(config.split_overlap)
+            .unwrap_or(10);
+        let format = value::<Format>(&ms, "format")?;
+        let files = values_t!(ms, "file", String).unwrap_or(config.files);
+        let stdin_filename = value_t!(ms, "stdin-filename", String)
+            .unwrap_or_else(|_| String::from("<stdin>"));
+        let unicode = !ms.is_present("no-unicode") && config.unicode.unwrap_or(true);
+        let unused_suppressions = ms.is_present("report-unused-suppressions");
+        let fix = ms.is_present("fix");
+        let fix_dry_run = ms.is_present("fix-dry-run");
+        let metrics = ms.is_present("metrics");
+        let fail_on = value(&ms, "fail-on")?.or(config.fail_on);
+        let min_severity = value(&ms, "min-severity")?.or(config.min_severity);
 
         Ok(Opt {
-            lints: lints,
-            style: style,
-            split: split,
-            unicode: unicode,
-            files: files,
+            lints,
+            style,
+            split,
+            split_overlap,
+            format,
+            unicode,
+            unused_suppressions,
+            fix,
+            fix_dry_run,
+            metrics,
+            fail_on,
+            min_severity,
+            severities: config.severity,
+            files,
+            exclude: config.exclude,
+            stdin_filename,
+            markup: markup::Options { html: config.html },
         })
     }
 }
+
+/// Parses the value of the flag `name`, if it was given. An invalid value is
+/// an error rather than falling back to the default.
+fn value<T>(ms: &ArgMatches, name: &str) -> Result<Option<T>, Error>
+where
+    T: FromStr,
+{
+    if ms.is_present(name) {
+        Ok(Some(value_t!(ms, name, T)?))
+    } else {
+        Ok(None)
+    }
+}
@@ -1,26 +1,17 @@
 use bytecount;
 use failure::Error;
 use memchr::memchr;
-use std::path::Path;
+use serde_json;
 use std::str;
-use termcolor::{ColorSpec, WriteColor};
+use termcolor::WriteColor;
 
 use temper::prose::*;
+use temper::readability::{Metric, Stats};
 use cli::*;
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct Colors {
-    pub path: ColorSpec,
-    pub error: ColorSpec,
-    pub warning: ColorSpec,
-    pub suggestion: ColorSpec,
-    pub matched: ColorSpec,
-}
-
 pub struct Printer<W> {
     pub wtr: W,
     pub style: Style,
-    pub colors: Colors,
     pub eol: u8,
 }
 
@@ -30,10 +21,44 @@ impl<W: WriteColor> Printer<W> {
         match self.style {
             Style::Line => self.write_match_line(m),
             Style::Verbose => self.write_match_verbose(m, context, moffset),
-            _ => unimplemented!(),
+            Style::Json => self.write_match_json(m),
         }
     }
 
+    /// Writes the score of every readability metric for the prose of `file`.
+    pub fn write_metrics(&mut self, file: &str, stats: &Stats) -> Result<(), Error> {
+        let s = match self.style {
+            Style::Json => {
+                let mut map = serde_json::Map::new();
+                map.insert(String::from("file"), json!(file));
+                for &metric in Metric::ALL.iter() {
+                    map.insert(metric.to_string(), json!(metric.score(stats)));
+                }
+                serde_json::to_string(&map)?
+            }
+            Style::Line | Style::Verbose => {
+                let scores: Vec<String> = Metric::ALL
+                    .iter()
+                    .map(|&metric| match metric.score(stats) {
+                        Some(score) => format!("{} {:.2}", metric, score),
+                        None => format!("{} -", metric),
+                    })
+                    .collect();
+                format!("{}: {}", file, scores.join(", "))
+            }
+        };
+
+        self.write(s.as_bytes())?;
+        self.write_eol(1)
+    }
+
+    fn write_match_json(&mut self, m: &Match) -> Result<(), Error> {
+        let s = serde_json::to_string(m)?;
+
+        self.write(s.as_bytes())?;
+        self.write_eol(1)
+    }
+
     fn write_match_line(&mut self, m: &Match) -> Result<(), Error> {
         let s = format!(
             "{}:{}:{} {}:{} {}",
@@ -55,8 +80,8 @@ impl<W: WriteColor> Printer<W> {
         let nlines = bytecount::count(context.as_bytes(), self.eol) + 1;
         let mut offsets = vec![0];
         let mut last = 0;
-        while let Some(i) = memchr(self.eol, context[last..].as_bytes()) {
-            offsets.push(i + 1);
+        while let Some(i) = memchr(self.eol, &context.as_bytes()[last..]) {
+            offsets.push(last + i + 1);
             last += i + 1;
         }
         offsets.push(last + context[last..].len());
@@ -80,7 +105,7 @@ impl<W: WriteColor> Printer<W> {
         self.write_eol(1)?;
 
         // TODO: Should we pull out regex?
-        let context = context.replace('\n', " ").replace('\r', " ");
+        let context = context.replace(['\n', '\r'], " ");
         let context = context.as_bytes();
 
         for i in 0..nlines {
@@ -137,10 +162,6 @@ impl<W: WriteColor> Printer<W> {
         self.write_eol(2)
     }
 
-    fn write_path<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
-        self.write(path.as_ref().to_string_lossy().as_bytes())
-    }
-
     fn write_eol(&mut self, count: usize) -> Result<(), Error> {
         let eol = self.eol;
         for _ in 0..count {
@@ -149,16 +170,6 @@ impl<W: WriteColor> Printer<W> {
         Ok(())
     }
 
-    fn write_colored<F>(&mut self, buf: &[u8], get_color: F) -> Result<(), Error>
-    where
-        F: Fn(&Colors) -> &ColorSpec,
-    {
-        self.wtr.set_color(get_color(&self.colors))?;
-        self.write(buf)?;
-        self.wtr.reset()?;
-        Ok(())
-    }
-
     fn write(&mut self, buf: &[u8]) -> Result<(), Error> {
         self.wtr.write_all(buf)?;
         Ok(())
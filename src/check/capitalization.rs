@@ -0,0 +1,98 @@
+//! Module `capitalization.rs` checks that text, such as a heading, follows a
+//! style of capitalization.
+
+use std::collections::HashMap;
+use strfmt::strfmt;
+
+use check::Hit;
+use lint::Case;
+use linter::CompiledLint;
+use scope::words;
+
+/// Words which stay lower case in the middle of a title.
+const MINOR: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "from", "if", "in", "into", "nor", "of",
+    "off", "on", "or", "per", "so", "the", "to", "up", "via", "vs", "with", "yet",
+];
+
+/// Checks the capitalization of each of `spans`, reporting the ones that
+/// don't follow the lint's style along with how they should be written.
+///
+/// Words are found in the masked `text`, but the suggested replacement is
+/// built from the `original`, so that any markup inside a span is kept. Words
+/// inside one of the lint's exceptions are left alone, as are words with
+/// capitals past their first letter, such as "API" or "GitHub".
+pub(crate) fn capitalization(
+    ix: usize,
+    cl: &CompiledLint,
+    original: &str,
+    text: &str,
+    spans: &[(usize, usize)],
+) -> Vec<Hit> {
+    let excepted = cl.excepted(text);
+    let style = cl.lint.style;
+    let mut res = Vec::new();
+
+    for &(start, end) in spans {
+        let ws = words(&text[start..end]);
+        let mut fixed = String::with_capacity(end - start);
+        let mut last = start;
+
+        for (i, &(s, e)) in ws.iter().enumerate() {
+            let (s, e) = (start + s, start + e);
+            let word = &original[s..e];
+            fixed.push_str(&original[last..s]);
+            last = e;
+
+            let kept = excepted.iter().any(|&(xs, xe)| xs <= s && e <= xe)
+                || (style != Case::Upper && word.chars().skip(1).any(char::is_uppercase))
+                || word == "I"
+                || word.starts_with("I'")
+                || word.starts_with("I’");
+            if kept {
+                fixed.push_str(word);
+                continue;
+            }
+
+            let lower = word.to_lowercase();
+            let capital = match style {
+                Case::Upper => {
+                    fixed.push_str(&word.to_uppercase());
+                    continue;
+                }
+                Case::Lower => false,
+                Case::Sentence => i == 0,
+                Case::Title => i == 0 || i == ws.len() - 1 || !MINOR.contains(&&lower[..]),
+            };
+
+            if capital {
+                let mut cs = lower.chars();
+                if let Some(c) = cs.next() {
+                    fixed.extend(c.to_uppercase());
+                    fixed.push_str(cs.as_str());
+                }
+            } else {
+                fixed.push_str(&lower);
+            }
+        }
+        fixed.push_str(&original[last..end]);
+
+        if fixed != original[start..end] {
+            let mut map = HashMap::new();
+            map.insert("match".to_string(), original[start..end].to_string());
+            map.insert("value".to_string(), fixed.clone());
+            map.insert("style".to_string(), style.to_string());
+
+            res.push(Hit {
+                lint: ix,
+                start,
+                end,
+                msg: strfmt(&cl.lint.msg, &map).unwrap_or_else(|_| cl.lint.msg.clone()),
+                replacements: vec![fixed],
+                suggestions: Vec::new(),
+            });
+        }
+    }
+
+    res
+}
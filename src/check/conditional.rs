@@ -0,0 +1,55 @@
+//! Module `conditional.rs` checks that one pattern only appears after another,
+//! such as an acronym only being used once it's been defined.
+
+use std::collections::HashMap;
+use strfmt::strfmt;
+
+use check::Hit;
+use linter::CompiledLint;
+
+/// Finds the matches of the lint's `first` pattern in `text` whose capture
+/// isn't also captured by an earlier (or enclosing) match of its `second`
+/// pattern. Matches inside one of the lint's exceptions are allowed.
+pub(crate) fn conditional(ix: usize, cl: &CompiledLint, text: &str) -> Vec<Hit> {
+    let (first, second) = match cl.conditional {
+        Some((ref first, ref second)) => (first, second),
+        None => return Vec::new(),
+    };
+
+    // Where each capture of the second pattern is first seen
+    let mut defined: HashMap<&str, usize> = HashMap::new();
+    for caps in second.captures_iter(text) {
+        let start = caps.get(0).unwrap().start();
+        let key = caps.get(1).or_else(|| caps.get(0)).unwrap().as_str();
+        defined.entry(key).or_insert(start);
+    }
+
+    let excepted = cl.excepted(text);
+
+    first
+        .captures_iter(text)
+        .filter_map(|caps| {
+            let whole = caps.get(0).unwrap();
+            let key = caps.get(1).unwrap_or(whole);
+            let allowed = defined.get(key.as_str()).is_some_and(|&d| d <= whole.start())
+                || excepted
+                    .iter()
+                    .any(|&(s, e)| s <= whole.start() && whole.end() <= e);
+            if allowed {
+                return None;
+            }
+
+            let mut map = HashMap::new();
+            map.insert("match".to_string(), whole.as_str());
+
+            Some(Hit {
+                lint: ix,
+                start: whole.start(),
+                end: whole.end(),
+                msg: strfmt(&cl.lint.msg, &map).unwrap_or_else(|_| cl.lint.msg.clone()),
+                replacements: Vec::new(),
+                suggestions: Vec::new(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,77 @@
+//! Module `consistency.rs` finds the places where a document mixes different
+//! spellings of the same word, such as `email` and `e-mail`.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use strfmt::strfmt;
+
+use check::Hit;
+use fix::preserve_case;
+use linter::CompiledLint;
+use prose::Match;
+
+/// Checks that each of `spans` uses only one spelling from each of the groups
+/// of the lint at index `ix`. The spelling used most often in a span wins, or
+/// the one used first if there's a tie, and every use of the others is
+/// reported. Both `matches` and `spans` must be sorted.
+pub(crate) fn consistency(
+    ix: usize,
+    cl: &CompiledLint,
+    matches: &[&Match],
+    spans: &[(usize, usize)],
+) -> Vec<Hit> {
+    let mut res = Vec::new();
+    let mut ms = matches.iter().peekable();
+
+    for &(start, end) in spans {
+        while ms.peek().is_some_and(|m| m.offset.start < start) {
+            ms.next();
+        }
+        let mut inside = Vec::new();
+        while let Some(m) = ms.next_if(|m| m.offset.start < end) {
+            inside.push(*m);
+        }
+
+        for group in &cl.alternatives {
+            // The matches of each spelling in the group, in order
+            let mut uses: Vec<Vec<&Match>> = vec![Vec::new(); group.len()];
+            for m in &inside {
+                if let Some(a) = group.iter().position(|r| r.is_match(&m.text)) {
+                    uses[a].push(*m);
+                }
+            }
+            if uses.iter().filter(|u| !u.is_empty()).count() < 2 {
+                continue;
+            }
+
+            let best = (0..uses.len())
+                .filter(|&a| !uses[a].is_empty())
+                .max_by_key(|&a| (uses[a].len(), Reverse(uses[a][0].offset.start)))
+                .unwrap();
+            let value = &uses[best][0].text;
+
+            for m in uses
+                .iter()
+                .enumerate()
+                .filter(|&(a, _)| a != best)
+                .flat_map(|(_, u)| u.iter())
+            {
+                let mut map = HashMap::new();
+                map.insert("match".to_string(), &m.text[..]);
+                map.insert("value".to_string(), &value[..]);
+
+                res.push(Hit {
+                    lint: ix,
+                    start: m.offset.start,
+                    end: m.offset.end,
+                    msg: strfmt(&cl.lint.msg, &map).unwrap_or_else(|_| cl.lint.msg.clone()),
+                    replacements: vec![preserve_case(&m.text, value)],
+                    suggestions: Vec::new(),
+                });
+            }
+        }
+    }
+
+    res.sort_by_key(|h| h.start);
+    res
+}
@@ -0,0 +1,87 @@
+//! Module `length.rs` reports sentences with too many words, and paragraphs
+//! with too many sentences.
+
+use std::collections::HashMap;
+use strfmt::strfmt;
+
+use check::Hit;
+use lint::{Lint, Scope};
+use scope::{sentences, words};
+
+/// Checks the length of every sentence and paragraph in `paragraphs` of `text`
+/// against the lint's `max_words` and `max_sentences`. Each hit spans all of
+/// the sentence or paragraph that's too long.
+pub(crate) fn length(
+    ix: usize,
+    lint: &Lint,
+    text: &str,
+    paragraphs: &[(usize, usize)],
+) -> Vec<Hit> {
+    let mut res = Vec::new();
+
+    let hit = |(start, end): (usize, usize), scope: Scope, count: usize, unit, limit: usize| {
+        let mut map = HashMap::new();
+        map.insert("match".to_string(), text[start..end].to_string());
+        map.insert("count".to_string(), count.to_string());
+        map.insert("unit".to_string(), String::from(unit));
+        map.insert("limit".to_string(), limit.to_string());
+        map.insert("scope".to_string(), scope.to_string());
+
+        Hit {
+            lint: ix,
+            start,
+            end,
+            msg: strfmt(&lint.msg, &map).unwrap_or_else(|_| lint.msg.clone()),
+            replacements: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    };
+
+    for &p in paragraphs {
+        let ss = sentences(text, p);
+
+        if let Some(max) = lint.max_sentences.filter(|&max| ss.len() > max) {
+            res.push(hit(p, Scope::Paragraph, ss.len(), "sentences", max));
+        }
+        if let Some(max) = lint.max_words {
+            for &(start, end) in &ss {
+                let count = words(&text[start..end]).len();
+                if count > max {
+                    res.push(hit((start, end), Scope::Sentence, count, "words", max));
+                }
+            }
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lint::Kind;
+
+    #[test]
+    fn length_limits() {
+        let lint = Lint {
+            name: String::from("temper.test"),
+            kind: Kind::Length,
+            msg: String::from("{scope}: {count} {unit}, at most {limit}"),
+            max_words: Some(4),
+            max_sentences: Some(2),
+            ..Lint::default()
+        };
+        let text = "Dr. Smith paid 3.50 dollars today. Fine.\n\nOne. Two. Three, e.g. four five six.";
+        let hits = length(0, &lint, text, &[(0, 40), (42, text.len())]);
+
+        let found: Vec<_> = hits.iter().map(|h| (&text[h.start..h.end], &h.msg[..])).collect();
+        assert_eq!(
+            vec![
+                ("Dr. Smith paid 3.50 dollars today.", "sentence: 5 words, at most 4"),
+                (&text[42..], "paragraph: 3 sentences, at most 2"),
+                ("Three, e.g. four five six.", "sentence: 6 words, at most 4"),
+            ],
+            found
+        );
+    }
+}
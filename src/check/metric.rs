@@ -0,0 +1,68 @@
+//! Module `metric.rs` reports the parts of a document which are harder (or
+//! easier) to read than a lint allows.
+
+use std::cmp;
+use std::collections::HashMap;
+use strfmt::strfmt;
+
+use check::Hit;
+use lint::Lint;
+use readability::Stats;
+
+/// Scores each of `spans` of `text` with the lint's readability formula,
+/// counting only the prose inside of `paragraphs`. A part whose score is past
+/// the lint's `max` or `min` is reported at its first line.
+pub(crate) fn metric(
+    ix: usize,
+    lint: &Lint,
+    text: &str,
+    paragraphs: &[(usize, usize)],
+    spans: &[(usize, usize)],
+) -> Vec<Hit> {
+    let metric = match lint.metric {
+        Some(m) => m,
+        None => return Vec::new(),
+    };
+    let mut res = Vec::new();
+
+    for &(start, end) in spans {
+        let inside: Vec<(usize, usize)> = paragraphs
+            .iter()
+            .map(|&(s, e)| (cmp::max(s, start), cmp::min(e, end)))
+            .filter(|&(s, e)| s < e)
+            .collect();
+        let score = match metric.score(&Stats::new(text, &inside)) {
+            Some(score) => score,
+            None => continue,
+        };
+
+        let crossed = if lint.max.is_some_and(|max| score > max) {
+            lint.max.map(|max| ("at most", max))
+        } else if lint.min.is_some_and(|min| score < min) {
+            lint.min.map(|min| ("at least", min))
+        } else {
+            None
+        };
+
+        if let Some((bound, limit)) = crossed {
+            let stop = text[start..end].find('\n').map_or(end, |i| start + i);
+            let mut map = HashMap::new();
+            map.insert("match".to_string(), text[start..stop].to_string());
+            map.insert("metric".to_string(), metric.to_string());
+            map.insert("score".to_string(), format!("{:.2}", score));
+            map.insert("bound".to_string(), bound.to_string());
+            map.insert("limit".to_string(), limit.to_string());
+
+            res.push(Hit {
+                lint: ix,
+                start,
+                end: stop,
+                msg: strfmt(&lint.msg, &map).unwrap_or_else(|_| lint.msg.clone()),
+                replacements: Vec::new(),
+                suggestions: Vec::new(),
+            });
+        }
+    }
+
+    res
+}
@@ -0,0 +1,31 @@
+//! Module `check` holds the lint kinds which look at all of a lint's matches in
+//! a document together, instead of reporting each of them on its own.
+
+mod capitalization;
+mod conditional;
+mod consistency;
+mod length;
+mod metric;
+mod occurrence;
+mod repetition;
+mod spelling;
+
+pub(crate) use self::capitalization::capitalization;
+pub(crate) use self::conditional::conditional;
+pub(crate) use self::consistency::consistency;
+pub(crate) use self::length::length;
+pub(crate) use self::metric::metric;
+pub(crate) use self::occurrence::occurrence;
+pub(crate) use self::repetition::repetition;
+pub(crate) use self::spelling::spelling;
+
+/// A finding of a lint in a buffer, before it's been placed in the document.
+pub(crate) struct Hit {
+    /// The index of the lint in `Linter::lints`.
+    pub lint: usize,
+    pub start: usize,
+    pub end: usize,
+    pub msg: String,
+    pub replacements: Vec<String>,
+    pub suggestions: Vec<String>,
+}
@@ -0,0 +1,111 @@
+//! Module `occurrence.rs` counts the matches of a lint in each part of a
+//! document, reporting the parts with too many or too few of them.
+
+use std::collections::HashMap;
+use strfmt::strfmt;
+
+use check::Hit;
+use lint::Lint;
+use prose::Match;
+
+/// Checks the number of `matches` of the lint at index `ix` in each of `spans`,
+/// which are the parts of `text` given by the lint's scope. Both `matches` and
+/// `spans` must be sorted.
+///
+/// A part with too many matches is reported at the first match past the
+/// limit, and a part with too few is reported at its first line.
+pub(crate) fn occurrence(
+    ix: usize,
+    lint: &Lint,
+    text: &str,
+    matches: &[&Match],
+    spans: &[(usize, usize)],
+) -> Vec<Hit> {
+    let mut res = Vec::new();
+    let mut ms = matches.iter().peekable();
+
+    let hit = |start: usize, end: usize, count: usize, bound: &str, limit: usize| {
+        let mut map = HashMap::new();
+        map.insert("match".to_string(), text[start..end].to_string());
+        map.insert("count".to_string(), count.to_string());
+        map.insert("bound".to_string(), bound.to_string());
+        map.insert("limit".to_string(), limit.to_string());
+        map.insert("scope".to_string(), lint.scope.to_string());
+
+        Hit {
+            lint: ix,
+            start,
+            end,
+            msg: strfmt(&lint.msg, &map).unwrap_or_else(|_| lint.msg.clone()),
+            replacements: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    };
+
+    for &(start, end) in spans {
+        while ms.peek().is_some_and(|m| m.offset.start < start) {
+            ms.next();
+        }
+        let mut inside = Vec::new();
+        while let Some(m) = ms.next_if(|m| m.offset.start < end) {
+            inside.push(m);
+        }
+        let count = inside.len();
+
+        // The linter only allows whole counts
+        if let Some(max) = lint.max.map(|n| n as usize).filter(|&max| count > max) {
+            let m = inside[max];
+            res.push(hit(m.offset.start, m.offset.end, count, "at most", max));
+        }
+        if let Some(min) = lint.min.map(|n| n as usize).filter(|&min| count < min) {
+            let stop = text[start..end].find('\n').map_or(end, |i| start + i);
+            res.push(hit(start, stop, count, "at least", min));
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lint::{Kind, Severity};
+    use prose::Offset;
+
+    fn found(start: usize, end: usize) -> Match {
+        Match {
+            file: String::from("test"),
+            line: 1,
+            column: start + 1,
+            lint: String::from("temper.test"),
+            severity: Severity::Warning,
+            msg: String::new(),
+            offset: Offset { start, end },
+            text: String::new(),
+            replacements: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn occurrence_limits() {
+        let lint = Lint {
+            name: String::from("temper.test"),
+            kind: Kind::Occurrence,
+            msg: String::from("{match}: {count}, {bound} {limit}"),
+            max: Some(1.0),
+            min: Some(1.0),
+            ..Lint::default()
+        };
+        let text = "a! b! c!\nd.\ne!";
+        let matches = [found(1, 2), found(4, 5), found(7, 8), found(13, 14)];
+        let refs: Vec<&Match> = matches.iter().collect();
+        let hits = occurrence(0, &lint, text, &refs, &[(0, 8), (9, 11), (12, 14)]);
+
+        let msgs: Vec<_> = hits.iter().map(|h| (h.start, &h.msg[..])).collect();
+        assert_eq!(
+            vec![(4, "!: 3, at most 1"), (9, "d.: 0, at least 1")],
+            msgs
+        );
+    }
+}
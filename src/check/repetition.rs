@@ -0,0 +1,61 @@
+//! Module `repetition.rs` finds words which are accidentally written twice in
+//! a row, such as "the the", even when they're split across lines.
+
+use std::collections::HashMap;
+use strfmt::strfmt;
+
+use check::Hit;
+use linter::CompiledLint;
+use markup::{crosses_hole, Region};
+use scope::{terminator, words};
+
+/// Finds consecutive repeated words in each of `spans` of `text`, ignoring
+/// case and any whitespace or punctuation between them, as in "it, it". Words
+/// in different sentences, as in "no. No", don't count, and neither do words
+/// with markup cut out of `regions` between them. Repetitions inside one of the
+/// lint's exceptions, like "had had", are allowed.
+pub(crate) fn repetition(
+    ix: usize,
+    cl: &CompiledLint,
+    text: &str,
+    regions: Option<&[Region]>,
+    spans: &[(usize, usize)],
+) -> Vec<Hit> {
+    let excepted = cl.excepted(text);
+    let mut res = Vec::new();
+
+    for &(start, end) in spans {
+        let mut prev: Option<(usize, usize)> = None;
+
+        for (s, e) in words(&text[start..end]) {
+            let (s, e) = (start + s, start + e);
+            if let Some((ps, pe)) = prev {
+                let word = &text[ps..pe];
+                let repeated = word.to_lowercase() == text[s..e].to_lowercase()
+                    && !text[pe..s].contains(terminator)
+                    && !crosses_hole(regions, pe, s)
+                    && !excepted.iter().any(|&(xs, xe)| xs <= ps && e <= xe);
+
+                if repeated {
+                    let mut map = HashMap::new();
+                    map.insert("match".to_string(), word);
+
+                    res.push(Hit {
+                        lint: ix,
+                        start: ps,
+                        end: e,
+                        msg: strfmt(&cl.lint.msg, &map).unwrap_or_else(|_| cl.lint.msg.clone()),
+                        // Keep the first word and any punctuation after it
+                        replacements: vec![String::from(text[ps..s].trim_end())],
+                        suggestions: Vec::new(),
+                    });
+                    prev = None;
+                    continue;
+                }
+            }
+            prev = Some((s, e));
+        }
+    }
+
+    res
+}
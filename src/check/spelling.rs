@@ -0,0 +1,104 @@
+//! Module `spelling.rs` reports the words of a document which aren't in a
+//! lint's dictionary.
+
+use std::collections::HashMap;
+use strfmt::strfmt;
+
+use check::Hit;
+use linter::CompiledLint;
+use scope::words;
+
+/// Checks the spelling of every word in `spans` of `text`, suggesting
+/// corrections for the ones which are misspelled. The corrections aren't
+/// replacements, since the dictionary's best guess is often wrong and
+/// shouldn't be applied by `--fix`. Words with digits in them, and words
+/// inside one of the lint's exceptions, are skipped.
+pub(crate) fn spelling(
+    ix: usize,
+    cl: &CompiledLint,
+    text: &str,
+    spans: &[(usize, usize)],
+) -> Vec<Hit> {
+    let dict = match cl.dictionary {
+        Some(ref dict) => dict,
+        None => return Vec::new(),
+    };
+    let excepted = cl.excepted(text);
+    // Suggestions for each word seen so far, or `None` if it's spelled right
+    let mut checked: HashMap<&str, Option<Vec<String>>> = HashMap::new();
+    let mut res = Vec::new();
+
+    for &(start, end) in spans {
+        for (s, e) in words(&text[start..end]) {
+            let (s, e) = (start + s, start + e);
+            let word = &text[s..e];
+            let skip = word.chars().any(|c| c.is_numeric())
+                || excepted.iter().any(|&(xs, xe)| xs <= s && e <= xe);
+            if skip {
+                continue;
+            }
+
+            let suggestions = checked.entry(word).or_insert_with(|| {
+                if dict.check(word) {
+                    None
+                } else {
+                    Some(dict.suggest(word))
+                }
+            });
+            let suggestions = match *suggestions {
+                Some(ref sug) => sug.clone(),
+                None => continue,
+            };
+
+            let mut map = HashMap::new();
+            map.insert("match".to_string(), word);
+
+            res.push(Hit {
+                lint: ix,
+                start: s,
+                end: e,
+                msg: strfmt(&cl.lint.msg, &map).unwrap_or_else(|_| cl.lint.msg.clone()),
+                replacements: Vec::new(),
+                suggestions,
+            });
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lint::{Kind, Lint};
+    use spelling::Dictionary;
+
+    #[test]
+    fn spelling_words() {
+        let mut dict = Dictionary::parse(
+            "SFX S Y 1\nSFX S 0 s .\n",
+            "4\nthe/S\ncat/S\nsat\non\n",
+        ).unwrap();
+        dict.add("temper");
+        let cl = CompiledLint {
+            lint: Lint {
+                kind: Kind::Spelling,
+                msg: String::from("{match} may be misspelled"),
+                ..Lint::default()
+            },
+            tokens: Vec::new(),
+            exceptions: None,
+            alternatives: Vec::new(),
+            conditional: None,
+            dictionary: Some(dict),
+        };
+        let text = "The cta sat on temper's 2nd cats, cta.";
+        let hits = spelling(0, &cl, text, &[(0, text.len())]);
+
+        let found: Vec<_> = hits.iter().map(|h| &text[h.start..h.end]).collect();
+        assert_eq!(vec!["cta", "cta"], found);
+        assert!(hits[0].replacements.is_empty());
+        assert_eq!(vec![String::from("cat")], hits[0].suggestions);
+        assert_eq!("cta may be misspelled", hits[0].msg);
+    }
+}
@@ -0,0 +1,336 @@
+//! Module `fix.rs` applies the replacements suggested by matches to the text
+//! they were found in, and renders the result as a diff.
+
+use std::cmp;
+
+use prose::{Match, Offset};
+
+/// Adapts `replacement` to the case of `original`, so that replacing
+/// "Utilize" with "use" gives "Use" and replacing "UTILIZE" gives "USE".
+pub fn preserve_case(original: &str, replacement: &str) -> String {
+    let mut letters = original.chars().filter(|c| c.is_alphabetic());
+    let first = match letters.next() {
+        Some(c) => c,
+        None => return String::from(replacement),
+    };
+    let rest: Vec<char> = letters.collect();
+
+    if first.is_uppercase() && !rest.is_empty() && rest.iter().all(|c| c.is_uppercase()) {
+        replacement.to_uppercase()
+    } else if first.is_uppercase() {
+        let mut cs = replacement.chars();
+        match cs.next() {
+            Some(c) => c.to_uppercase().chain(cs).collect(),
+            None => String::new(),
+        }
+    } else {
+        String::from(replacement)
+    }
+}
+
+/// The result of applying fixes to a text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Fixed {
+    pub text: String,
+    /// The indices of the matches whose fixes were applied.
+    pub applied: Vec<usize>,
+    /// The range of the original text each applied fix replaced, along with
+    /// the range of its replacement in the fixed text, in order.
+    pub changes: Vec<(Offset, Offset)>,
+}
+
+/// Applies the first suggested replacement of each match to `text`.
+///
+/// When fixes overlap, the one starting first wins (or, for fixes of the same
+/// range, the one appearing first in `matches`), and the others are left
+/// unapplied.
+pub fn apply(text: &str, matches: &[Match]) -> Fixed {
+    let mut order: Vec<usize> = (0..matches.len())
+        .filter(|&i| !matches[i].replacements.is_empty())
+        .collect();
+    order.sort_by_key(|&i| (matches[i].offset.start, matches[i].offset.end));
+
+    let mut res = String::with_capacity(text.len());
+    let mut applied: Vec<usize> = Vec::new();
+    let mut changes = Vec::new();
+    let mut last = 0;
+
+    for i in order {
+        let m = &matches[i];
+        let clash = applied.last().is_some_and(|&k| {
+            m.offset.start < last || matches[k].offset.start == m.offset.start
+        });
+        if clash {
+            continue;
+        }
+
+        res.push_str(&text[last..m.offset.start]);
+        let start = res.len();
+        res.push_str(&m.replacements[0]);
+        changes.push((m.offset, Offset { start, end: res.len() }));
+        last = m.offset.end;
+        applied.push(i);
+    }
+    res.push_str(&text[last..]);
+    applied.sort();
+
+    Fixed {
+        text: res,
+        applied,
+        changes,
+    }
+}
+
+/// Renders the fixes that turned `old` into `fixed` as a unified diff of
+/// `path`, with three lines of context around each change. Returns an empty
+/// string if nothing changed.
+pub fn diff(path: &str, old: &str, fixed: &Fixed) -> String {
+    const CONTEXT: usize = 3;
+
+    let new = &fixed.text[..];
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = edits(old, fixed);
+    if ops.iter().all(|op| *op == Edit::Keep) {
+        return String::new();
+    }
+
+    let path = path.trim_start_matches("./").trim_start_matches('/');
+    let mut res = format!("--- a/{}\n+++ b/{}\n", path, path);
+
+    // Walk through the edits, grouping changes that are close together into
+    // hunks
+    let mut k = 0;
+    while k < ops.len() {
+        if ops[k] == Edit::Keep {
+            k += 1;
+            continue;
+        }
+
+        let start = k.saturating_sub(CONTEXT);
+        let mut last = k;
+        let mut end = k;
+        while end < ops.len() {
+            if ops[end] != Edit::Keep {
+                last = end;
+            } else if end - last > 2 * CONTEXT {
+                break;
+            }
+            end += 1;
+        }
+        let end = cmp::min(last + 1 + CONTEXT, ops.len());
+
+        let (ai, bi) = position(&ops[..start]);
+        let (an, bn) = position(&ops[start..end]);
+        res.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            ai + cmp::min(an, 1),
+            an,
+            bi + cmp::min(bn, 1),
+            bn
+        ));
+
+        let (mut i, mut j) = (ai, bi);
+        for op in &ops[start..end] {
+            let (prefix, line) = match *op {
+                Edit::Keep => {
+                    i += 1;
+                    j += 1;
+                    (' ', a[i - 1])
+                }
+                Edit::Delete => {
+                    i += 1;
+                    ('-', a[i - 1])
+                }
+                Edit::Insert => {
+                    j += 1;
+                    ('+', b[j - 1])
+                }
+            };
+            res.push(prefix);
+            res.push_str(line);
+            if !line.ends_with('\n') {
+                res.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+
+        k = end;
+    }
+
+    res
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// Counts the lines of the old and new text covered by `ops`.
+fn position(ops: &[Edit]) -> (usize, usize) {
+    ops.iter().fold((0, 0), |(i, j), op| match *op {
+        Edit::Keep => (i + 1, j + 1),
+        Edit::Delete => (i + 1, j),
+        Edit::Insert => (i, j + 1),
+    })
+}
+
+/// Lists the line edits turning `old` into the text of `fixed`. Only the lines
+/// touched by a fix are compared, so this takes time linear in the length of
+/// the texts however far apart the fixes are.
+fn edits(old: &str, fixed: &Fixed) -> Vec<Edit> {
+    let new = &fixed.text[..];
+    let lines = |s: &str| s.split_inclusive('\n').count();
+
+    // The changed lines, as byte ranges of whole lines in the old text and the
+    // ranges they became in the new one
+    let mut blocks: Vec<((usize, usize), (usize, usize))> = Vec::new();
+    for &(o, n) in &fixed.changes {
+        // The line holding the last changed byte ends the block, which is the
+        // changed text itself if that ends with a newline
+        let start = old[..o.start].rfind('\n').map_or(0, |i| i + 1);
+        let end = if old[o.start..o.end].ends_with('\n') {
+            o.end
+        } else {
+            old[o.end..].find('\n').map_or(old.len(), |i| o.end + i + 1)
+        };
+        // The text around each change is the same in both, only shifted
+        let (ns, ne) = (n.start - (o.start - start), n.end + (end - o.end));
+
+        match blocks.last_mut() {
+            Some(&mut ((_, ref mut oe), (_, ref mut nw))) if start < *oe => {
+                *oe = end;
+                *nw = ne;
+            }
+            _ => blocks.push(((start, end), (ns, ne))),
+        }
+    }
+
+    let mut res = Vec::new();
+    let mut last = 0;
+    for ((os, oe), (ns, ne)) in blocks {
+        res.extend(vec![Edit::Keep; lines(&old[last..os])]);
+
+        let a: Vec<&str> = old[os..oe].split_inclusive('\n').collect();
+        let b: Vec<&str> = new[ns..ne].split_inclusive('\n').collect();
+        let pre = a.iter().zip(&b).take_while(|&(x, y)| x == y).count();
+        let suf = a[pre..]
+            .iter()
+            .rev()
+            .zip(b[pre..].iter().rev())
+            .take_while(|&(x, y)| x == y)
+            .count();
+
+        res.extend(vec![Edit::Keep; pre]);
+        res.extend(vec![Edit::Delete; a.len() - pre - suf]);
+        res.extend(vec![Edit::Insert; b.len() - pre - suf]);
+        res.extend(vec![Edit::Keep; suf]);
+        last = oe;
+    }
+    res.extend(vec![Edit::Keep; lines(&old[last..])]);
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lint::Severity;
+    use prose::Offset;
+
+    fn fix(start: usize, end: usize, replacement: &str) -> Match {
+        Match {
+            file: String::from("test"),
+            line: 1,
+            column: start + 1,
+            lint: String::from("temper.test"),
+            severity: Severity::Warning,
+            msg: String::new(),
+            offset: Offset { start, end },
+            text: String::new(),
+            replacements: vec![String::from(replacement)],
+            suggestions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn fix_preserve_case() {
+        assert_eq!("use", preserve_case("utilize", "use"));
+        assert_eq!("Use", preserve_case("Utilize", "use"));
+        assert_eq!("USE", preserve_case("UTILIZE", "use"));
+        assert_eq!("in order", preserve_case("iN", "in order"));
+        assert_eq!("A", preserve_case("I", "a"));
+    }
+
+    #[test]
+    fn fix_apply_overlapping() {
+        let text = "We utilize it and leverage it.";
+        let matches = vec![
+            fix(3, 10, "use"),
+            fix(3, 13, "use it"),
+            fix(3, 10, "employ"),
+            fix(18, 26, "use"),
+        ];
+        let fixed = apply(text, &matches);
+
+        assert_eq!("We use it and use it.", fixed.text);
+        assert_eq!(vec![0, 3], fixed.applied);
+
+        // Suggestions are only guesses, and never applied
+        let guess = Match {
+            replacements: Vec::new(),
+            suggestions: vec![String::from("use")],
+            ..fix(3, 10, "")
+        };
+        let fixed = apply(text, &[guess]);
+        assert_eq!(text, fixed.text);
+        assert!(fixed.applied.is_empty());
+    }
+
+    #[test]
+    fn fix_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let fixed = apply(old, &[fix(8, 9, "E")]);
+
+        assert_eq!("a\nb\nc\nd\nE\nf\ng\nh\ni\nj\n", fixed.text);
+        assert_eq!(
+            "--- a/x\n+++ b/x\n@@ -2,7 +2,7 @@\n b\n c\n d\n-e\n+E\n f\n g\n h\n",
+            diff("x", old, &fixed)
+        );
+        assert_eq!("", diff("x", old, &apply(old, &[])));
+
+        // A fix ending in a multibyte character
+        let old = "I like the café.\nMore.\n";
+        let fixed = apply(old, &[fix(11, 16, "coffee")]);
+        assert_eq!(
+            "--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-I like the café.\n+I like the coffee.\n More.\n",
+            diff("x", old, &fixed)
+        );
+    }
+
+    #[test]
+    fn fix_diff_lines() {
+        // Fixes which remove and add lines, and two on the same line
+        let old = "a\nb\nc\n";
+        let fixed = apply(old, &[fix(0, 2, ""), fix(4, 5, "x\ny"), fix(5, 6, "z\n")]);
+
+        assert_eq!("b\nx\nyz\n", fixed.text);
+        assert_eq!(
+            "--- a/x\n+++ b/x\n@@ -1,3 +1,3 @@\n-a\n b\n-c\n+x\n+yz\n",
+            diff("x", old, &fixed)
+        );
+    }
+
+    #[test]
+    fn fix_diff_far_apart() {
+        let old: String = (0..100_000).map(|i| format!("line {}\n", i)).collect();
+        let end = old.len() - 1;
+        let fixed = apply(&old, &[fix(0, 4, "LINE"), fix(end - 5, end, "LAST")]);
+
+        let diff = diff("x", &old, &fixed);
+        assert!(diff.contains("-line 0\n+LINE 0\n"));
+        assert!(diff.contains("-line 99999\n+line LAST\n"));
+        assert_eq!(2, diff.matches("@@ -").count());
+    }
+}
@@ -1,14 +1,27 @@
 extern crate bytecount;
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
 extern crate failure;
 extern crate memchr;
 extern crate ordermap;
 extern crate rayon;
 extern crate regex;
+extern crate regex_syntax;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate strfmt;
 
+mod check;
+pub mod fix;
 pub mod lint;
+pub mod linter;
+pub mod markup;
 pub mod prose;
+pub mod readability;
+pub mod reflow;
+pub mod scope;
+pub mod spelling;
+pub mod suppress;
 mod util;
@@ -6,28 +6,113 @@ use std::collections::HashMap;
 use std::fmt;
 use std::fs;
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use strfmt::strfmt;
 use ordermap::OrderMap;
 
-#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+use readability::Metric;
+
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Severity {
     Info,
     Suggestion,
+    #[default]
     Warning,
     Error,
 }
 
-impl Default for Severity {
-    fn default() -> Self {
-        Severity::Warning
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
     }
 }
 
-impl fmt::Display for Severity {
+impl FromStr for Severity {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "info" => Ok(Severity::Info),
+            "suggestion" => Ok(Severity::Suggestion),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format_err!("unknown severity `{}`", s)),
+        }
+    }
+}
+
+/// What a lint checks for.
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// Reports every match of the lint's tokens and mapping.
+    #[default]
+    Existence,
+    /// Reports parts of a document with too many or too few matches of the
+    /// lint's tokens and mapping.
+    Occurrence,
+    /// Reports the less common spellings of a word when a document uses more
+    /// than one of them.
+    Consistency,
+    /// Reports words which are written twice in a row.
+    Repetition,
+    /// Reports matches of the lint's `first` pattern which aren't preceded by
+    /// a matching `second` pattern, such as acronyms used before they're
+    /// defined.
+    Conditional,
+    /// Reports text, such as headings, which isn't capitalized in the lint's
+    /// `style`.
+    Capitalization,
+    /// Reports words which aren't in the lint's dictionary.
+    Spelling,
+    /// Reports parts of a document whose readability score is past a limit.
+    Metric,
+    /// Reports sentences with more than `max_words` words, and paragraphs
+    /// with more than `max_sentences` sentences.
+    Length,
+}
+
+/// The parts of a document that a lint looks at one at a time.
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    #[default]
+    Document,
+    Paragraph,
+    Sentence,
+    Line,
+    Heading,
+    /// A heading and everything up to the next one. The prose before the
+    /// first heading, or all of it in plain text, is a section of its own.
+    Section,
+    Admonition,
+}
+
+/// A style of capitalization.
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Case {
+    /// Every word is capitalized, except for short articles, conjunctions
+    /// and prepositions in the middle.
+    Title,
+    /// Only the first word is capitalized.
+    #[default]
+    Sentence,
+    Lower,
+    Upper,
+}
+
+impl fmt::Display for Case {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
     }
 }
 
@@ -41,42 +126,153 @@ struct TomlLint {
 #[derive(Deserialize)]
 struct TomlLintFields {
     name: String,
+    #[serde(default)] kind: Kind,
     #[serde(default)] severity: Severity,
-    #[serde(default = "default_msg")] msg: String,
+    msg: Option<String>,
     #[serde(default = "default_msg_mapping")] msg_mapping: String,
-    #[serde(default = "default_regex")] regex: String,
+    regex: Option<String>,
     #[serde(default = "default_tokens")] tokens: Vec<String>,
+    #[serde(default)] normalize_whitespace: bool,
+    #[serde(default)] ignorecase: bool,
+    #[serde(default)] nonword: bool,
+    #[serde(default)] prefix: String,
+    #[serde(default)] suffix: String,
+    #[serde(default)] exceptions: Vec<String>,
+    scope: Option<Scope>,
+    max: Option<f64>,
+    min: Option<f64>,
+    #[serde(default)] groups: Vec<Vec<String>>,
+    first: Option<String>,
+    second: Option<String>,
+    #[serde(default)] style: Case,
+    dictionary: Option<String>,
+    #[serde(default)] accept: Vec<String>,
+    metric: Option<Metric>,
+    max_words: Option<usize>,
+    max_sentences: Option<usize>,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Lint {
     pub name: String,
+    pub kind: Kind,
     pub severity: Severity,
     pub msg: String,
     pub msg_mapping: String,
     pub mapping: OrderMap<String, Option<String>>,
+    /// Whether the lint is run over whole paragraphs with their whitespace
+    /// collapsed, so that it matches phrases which are wrapped across lines.
+    pub normalize_whitespace: bool,
+    /// Patterns of text in which matches of the lint are allowed; a match is
+    /// dropped if it lies within a match of any of them.
+    pub exceptions: Vec<String>,
+    pub scope: Scope,
+    /// The most matches allowed in each part of the document, for occurrence
+    /// lints, or the highest score allowed, for metric lints. Counts need to
+    /// be whole numbers, but scores needn't.
+    pub max: Option<f64>,
+    /// The fewest matches allowed in each part of the document, for
+    /// occurrence lints, or the lowest score allowed, for metric lints.
+    pub min: Option<f64>,
+    /// Groups of interchangeable spellings, of which a document should only
+    /// use one, for consistency lints.
+    pub groups: Vec<Vec<String>>,
+    /// The pattern whose matches need to be preceded by a match of `second`
+    /// with the same capture, for conditional lints. Both patterns capture
+    /// their first group, or all of their match if they have no groups.
+    pub first: Option<String>,
+    pub second: Option<String>,
+    /// The capitalization expected, for capitalization lints.
+    pub style: Case,
+    /// The Hunspell dictionary to check words against, for spelling lints.
+    pub dictionary: Option<PathBuf>,
+    /// Lists of extra words to accept, for spelling lints.
+    pub accept: Vec<PathBuf>,
+    /// The readability formula used, for metric lints, whose `max` and `min`
+    /// are limits on its score.
+    pub metric: Option<Metric>,
+    /// The most words allowed in a sentence, for length lints.
+    pub max_words: Option<usize>,
+    /// The most sentences allowed in a paragraph, for length lints.
+    pub max_sentences: Option<usize>,
 }
 
 impl From<TomlLint> for Lint {
     fn from(mut toml: TomlLint) -> Self {
-        for token in toml.lint.tokens {
+        for token in toml.lint.tokens.drain(..) {
             toml.mapping.insert(token, None);
         }
 
-        let rtemp = toml.lint.regex;
-        let mut newmap = OrderMap::new();
+        let fields = &toml.lint;
+        let rtemp = match fields.regex {
+            Some(ref regex) => regex.clone(),
+            None if fields.nonword => String::from("{regex}"),
+            None => default_regex(),
+        };
         let mut regex = HashMap::with_capacity(1);
-        for (item, v) in toml.mapping {
+        let mut pattern = |item: String| {
             regex.insert("regex".to_owned(), item);
-            newmap.insert(strfmt(&rtemp, &regex).unwrap(), v);
+            let p = format!(
+                "{}{}{}",
+                fields.prefix,
+                strfmt(&rtemp, &regex).unwrap(),
+                fields.suffix
+            );
+            if fields.ignorecase {
+                format!("(?i:{})", p)
+            } else {
+                p
+            }
+        };
+
+        let mut newmap = OrderMap::new();
+        for (item, v) in toml.mapping {
+            newmap.insert(pattern(item), v);
+        }
+        let exceptions = fields.exceptions.iter().cloned().map(&mut pattern).collect();
+        let groups: Vec<Vec<String>> = fields
+            .groups
+            .iter()
+            .map(|g| g.iter().cloned().map(&mut pattern).collect())
+            .collect();
+        for alternative in groups.iter().flat_map(|g| g.iter()) {
+            newmap.insert(alternative.clone(), None);
         }
+        let ignorecase = |p: &String| {
+            if fields.ignorecase {
+                format!("(?i:{})", p)
+            } else {
+                p.clone()
+            }
+        };
+        let first = fields.first.as_ref().map(ignorecase);
+        let second = fields.second.as_ref().map(ignorecase);
 
+        let kind = toml.lint.kind;
         Lint {
             name: toml.lint.name,
+            kind,
             severity: toml.lint.severity,
-            msg: toml.lint.msg,
+            msg: toml.lint.msg.unwrap_or_else(|| default_msg(kind)),
             msg_mapping: toml.lint.msg_mapping,
             mapping: newmap,
+            normalize_whitespace: toml.lint.normalize_whitespace,
+            exceptions,
+            scope: toml.lint.scope.unwrap_or(match kind {
+                Kind::Capitalization => Scope::Heading,
+                _ => Scope::Document,
+            }),
+            max: toml.lint.max,
+            min: toml.lint.min,
+            groups,
+            first,
+            second,
+            style: toml.lint.style,
+            dictionary: toml.lint.dictionary.map(PathBuf::from),
+            accept: toml.lint.accept.iter().map(PathBuf::from).collect(),
+            metric: toml.lint.metric,
+            max_words: toml.lint.max_words,
+            max_sentences: toml.lint.max_sentences,
         }
     }
 }
@@ -87,10 +283,17 @@ pub type Lintset = Vec<Lint>;
 pub fn linters<T: AsRef<Path>>(paths: Vec<T>) -> Result<Lintset, Error> {
     let mut res: Lintset = Vec::new();
     for path in paths {
-        let mut f = fs::File::open(path)?;
+        let mut f = fs::File::open(&path)?;
         let mut contents = String::new();
         f.read_to_string(&mut contents)?;
-        let lint: Lint = <Lint as From<TomlLint>>::from(toml::from_str(&contents)?);
+        let mut lint: Lint = <Lint as From<TomlLint>>::from(toml::from_str(&contents)?);
+
+        // Files named by a lint are relative to the lint itself
+        let dir = path.as_ref().parent().unwrap_or_else(|| Path::new(""));
+        for p in lint.dictionary.iter_mut().chain(lint.accept.iter_mut()) {
+            *p = dir.join(&p);
+        }
+
         res.push(lint);
     }
     Ok(res)
@@ -100,8 +303,18 @@ fn default_mapping() -> OrderMap<String, Option<String>> {
     OrderMap::new()
 }
 
-fn default_msg() -> String {
-    String::from("{match} is a usage error")
+fn default_msg(kind: Kind) -> String {
+    match kind {
+        Kind::Existence => String::from("{match} is a usage error"),
+        Kind::Occurrence => String::from("Found {count} in this {scope}, expected {bound} {limit}"),
+        Kind::Consistency => String::from("Use {value} instead of {match} for consistency"),
+        Kind::Repetition => String::from("{match} is repeated"),
+        Kind::Conditional => String::from("{match} is used before it's defined"),
+        Kind::Capitalization => String::from("{match} should be in {style} case"),
+        Kind::Spelling => String::from("{match} may be misspelled"),
+        Kind::Metric => String::from("The {metric} score is {score}, expected {bound} {limit}"),
+        Kind::Length => String::from("This {scope} has {count} {unit}, expected at most {limit}"),
+    }
 }
 
 fn default_msg_mapping() -> String {
@@ -120,13 +333,14 @@ fn default_tokens() -> Vec<String> {
 mod tests {
     use super::*;
 
-    const COMPLETE: &'static str = "\
+    const COMPLETE: &str = "\
 [lint]
 name = 'temper.test.complete'
 severity = 'error'
 msg = 'This is a complete toml lintset. Match: {match}'
 msg_mapping = 'This is a complete toml lintset. {match}: {value}'
 regex = 'f {regex} f'
+normalize_whitespace = true
 
 tokens = ['a', '(?-u:b)', 'c']
 
@@ -137,7 +351,7 @@ dinner = 'true'
 dessert = 'false'
 ";
 
-    const DEFAULTS: &'static str = "\
+    const DEFAULTS: &str = "\
 [lint]
 name = 'temper.test.defaults'
 
@@ -147,7 +361,27 @@ tokens = ['k']
 hello = 'world'
 ";
 
-    const UNNAMED: &'static str = "
+    const EXISTENCE: &str = "\
+[lint]
+name = 'temper.test.existence'
+kind = 'existence'
+ignorecase = true
+nonword = true
+prefix = '(?:a|an) '
+suffix = '!'
+exceptions = ['x']
+
+tokens = ['b']
+";
+
+    const CONSISTENCY: &str = "\
+[lint]
+name = 'temper.test.consistency'
+kind = 'consistency'
+groups = [['email', 'e-mail'], ['OK', 'okay']]
+";
+
+    const UNNAMED: &str = "
 [lint]
 msg = 'This lint has no name, and an error should be returned.'
 severity = 'error'
@@ -168,10 +402,13 @@ tokens = ['whatever']
 
         let correct = Lint {
             name: String::from("temper.test.complete"),
+            kind: Kind::Existence,
             severity: Severity::Error,
             msg: String::from("This is a complete toml lintset. Match: {match}"),
             msg_mapping: String::from("This is a complete toml lintset. {match}: {value}"),
             mapping: correct_mapping,
+            normalize_whitespace: true,
+            ..Lint::default()
         };
 
         assert_eq!(
@@ -183,15 +420,21 @@ tokens = ['whatever']
     #[test]
     fn lint_parse_defaults() {
         let mut correct_mapping = OrderMap::new();
-        correct_mapping.insert(String::from(r"\bk\b"), None);
-        correct_mapping.insert(String::from(r"\bhello\b"), Some(String::from("world")));
+        correct_mapping.insert(String::from(r"(?-u:\b)k(?-u:\b)"), None);
+        correct_mapping.insert(
+            String::from(r"(?-u:\b)hello(?-u:\b)"),
+            Some(String::from("world")),
+        );
 
         let correct = Lint {
             name: String::from("temper.test.defaults"),
+            kind: Kind::Existence,
             severity: Severity::Warning,
-            msg: default_msg(),
+            msg: default_msg(Kind::Existence),
             msg_mapping: default_msg_mapping(),
             mapping: correct_mapping,
+            normalize_whitespace: false,
+            ..Lint::default()
         };
 
         assert_eq!(
@@ -200,6 +443,68 @@ tokens = ['whatever']
         );
     }
 
+    #[test]
+    fn lint_parse_existence() {
+        let lint = <Lint as From<TomlLint>>::from(toml::from_str(EXISTENCE).unwrap());
+
+        assert_eq!(Kind::Existence, lint.kind);
+        assert_eq!(
+            vec![&String::from("(?i:(?:a|an) b!)")],
+            lint.mapping.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(vec![String::from("(?i:(?:a|an) x!)")], lint.exceptions);
+        assert!(toml::from_str::<TomlLint>("[lint]\nname = 'a'\nkind = 'nope'\n").is_err());
+    }
+
+    #[test]
+    fn lint_parse_consistency() {
+        let lint = <Lint as From<TomlLint>>::from(toml::from_str(CONSISTENCY).unwrap());
+
+        assert_eq!(Kind::Consistency, lint.kind);
+        assert_eq!("Use {value} instead of {match} for consistency", lint.msg);
+        assert_eq!(2, lint.groups.len());
+        assert_eq!(r"(?-u:\b)e-mail(?-u:\b)", lint.groups[0][1]);
+        assert_eq!(4, lint.mapping.len());
+    }
+
+    #[test]
+    fn lint_parse_capitalization() {
+        let lint = <Lint as From<TomlLint>>::from(
+            toml::from_str("[lint]\nname = 'a'\nkind = 'capitalization'\nstyle = 'title'\n")
+                .unwrap(),
+        );
+
+        assert_eq!(Scope::Heading, lint.scope);
+        assert_eq!(Case::Title, lint.style);
+        assert_eq!("{match} should be in {style} case", lint.msg);
+    }
+
+    #[test]
+    fn lint_parse_metric() {
+        let toml = "[lint]\nname = 'a'\nkind = 'metric'\nmetric = 'flesch-kincaid'\n";
+        let lint = <Lint as From<TomlLint>>::from(
+            toml::from_str(&format!("{}max = 12.5\nmin = 2\n", toml)).unwrap(),
+        );
+
+        assert_eq!(Kind::Metric, lint.kind);
+        assert_eq!(Some(12.5), lint.max);
+        assert_eq!(Some(2.0), lint.min);
+    }
+
+    #[test]
+    fn severity_order() {
+        assert!(Severity::Info < Severity::Suggestion);
+        assert!(Severity::Suggestion < Severity::Warning);
+        assert!(Severity::Warning < Severity::Error);
+    }
+
+    #[test]
+    fn severity_from_str() {
+        assert_eq!(Severity::Error, "error".parse::<Severity>().unwrap());
+        assert_eq!(Severity::Info, "Info".parse::<Severity>().unwrap());
+        assert!("fatal".parse::<Severity>().is_err());
+    }
+
     #[test]
     fn lint_parse_unnamed() {
         assert!(toml::from_str::<TomlLint>(UNNAMED).is_err());
@@ -0,0 +1,374 @@
+//! Module `linter.rs` compiles a `Lintset` into the regular expressions used to
+//! search prose, so that they only have to be built once per run.
+
+use failure::Error;
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+use regex_syntax::ParserBuilder;
+use regex_syntax::hir::{
+    Class, GroupKind, Hir, HirKind, Literal, RepetitionKind, RepetitionRange,
+};
+use std::cmp;
+
+use lint::*;
+use spelling::Dictionary;
+
+/// A lint whose plain tokens have been compiled.
+#[derive(Debug)]
+pub struct CompiledLint {
+    pub lint: Lint,
+    /// Alternations of the lint's plain tokens, partitioned so that no single
+    /// regex grows too large. Tokens with capture groups are compiled on their
+    /// own instead, so that their groups keep their numbers.
+    pub tokens: Vec<Regex>,
+    /// An alternation of the lint's exceptions, if it has any.
+    pub exceptions: Option<Regex>,
+    /// The lint's groups of spellings, each of which only matches a whole
+    /// string.
+    pub alternatives: Vec<Vec<Regex>>,
+    /// The lint's `first` and `second` patterns, for conditional lints.
+    pub conditional: Option<(Regex, Regex)>,
+    /// The lint's dictionary and accepted words, for spelling lints.
+    pub dictionary: Option<Dictionary>,
+}
+
+impl CompiledLint {
+    /// Finds the byte ranges of `text` in which the lint's exceptions allow
+    /// its matches.
+    pub fn excepted(&self, text: &str) -> Vec<(usize, usize)> {
+        match self.exceptions {
+            Some(ref regex) => regex
+                .find_iter(text)
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// A single mapping entry of a lint, along with its replacement value.
+#[derive(Debug)]
+pub struct Mapped {
+    /// The index of the owning lint in `Linter::lints`.
+    pub lint: usize,
+    pub regex: Regex,
+    pub value: String,
+}
+
+/// A compiled `Lintset`, which can be shared between threads and reused for
+/// every `Prose` that is checked.
+#[derive(Debug)]
+pub struct Linter {
+    pub lints: Vec<CompiledLint>,
+    /// Every mapping pattern of every lint; a hit in the set is resolved
+    /// through the entry at the same index of `mapped`.
+    pub set: RegexSet,
+    pub mapped: Vec<Mapped>,
+    /// The most line breaks that a match of any lint can span, or `None` if
+    /// there's no limit. Lints run over reflowed paragraphs aren't counted.
+    pub newlines: Option<usize>,
+    /// Whether any lint is run over reflowed paragraphs.
+    pub reflowed: bool,
+}
+
+impl Linter {
+    pub fn new(lintset: Lintset, unicode: bool) -> Result<Linter, Error> {
+        let mut lints = Vec::with_capacity(lintset.len());
+        let mut patterns = Vec::new();
+        let mut mapped = Vec::new();
+        let mut nl = Some(0);
+
+        for (ix, lint) in lintset.into_iter().enumerate() {
+            if lint.kind == Kind::Occurrence && lint.max.is_none() && lint.min.is_none() {
+                bail!("lint `{}` needs a `max` or `min` to count occurrences", lint.name);
+            }
+            if lint.kind == Kind::Occurrence
+                && lint.max.into_iter().chain(lint.min).any(|n| n < 0.0 || n.fract() != 0.0)
+            {
+                bail!("lint `{}` needs a whole `max` and `min` to count occurrences", lint.name);
+            }
+            if lint.kind == Kind::Metric
+                && (lint.metric.is_none() || (lint.max.is_none() && lint.min.is_none()))
+            {
+                bail!("lint `{}` needs a `metric` and a `max` or `min` score", lint.name);
+            }
+            if lint.kind == Kind::Length && lint.max_words.is_none() && lint.max_sentences.is_none()
+            {
+                bail!("lint `{}` needs a `max_words` or `max_sentences`", lint.name);
+            }
+            if lint.kind == Kind::Consistency
+                && (lint.groups.is_empty() || lint.groups.iter().any(|g| g.len() < 2))
+            {
+                bail!(
+                    "lint `{}` needs `groups` of at least two spellings each",
+                    lint.name
+                );
+            }
+
+            let (grouped, rs): (Vec<&str>, Vec<&str>) = lint.mapping
+                .iter()
+                .filter(|x| x.1.is_none())
+                .map(|x| &x.0[..])
+                .partition(|k| has_captures(k, unicode));
+
+            // Exceptions need to be seen whole to tell whether they cover a match
+            let all = lint.mapping.keys().chain(lint.exceptions.iter());
+            for k in all.filter(|_| !lint.normalize_whitespace) {
+                nl = match (nl, max_newlines(k, unicode)) {
+                    (Some(a), Some(b)) => Some(cmp::max(a, b)),
+                    _ => None,
+                };
+            }
+
+            let mut tokens = Vec::new();
+            if !rs.is_empty() {
+                let rps = regexes_per_partition(rs.len());
+                for slice in rs.chunks(rps) {
+                    let regex = slice
+                        .iter()
+                        .map(|s| format!("(?:{})", s))
+                        .collect::<Vec<_>>()
+                        .join("|");
+                    tokens.push(RegexBuilder::new(&regex).unicode(unicode).build()?);
+                }
+            }
+            for k in grouped {
+                tokens.push(RegexBuilder::new(k).unicode(unicode).build()?);
+            }
+
+            for (k, v) in lint.mapping.iter() {
+                if let Some(v) = v {
+                    mapped.push(Mapped {
+                        lint: ix,
+                        regex: RegexBuilder::new(k).unicode(unicode).build()?,
+                        value: v.clone(),
+                    });
+                    patterns.push(k.clone());
+                }
+            }
+
+            let exceptions = if lint.exceptions.is_empty() {
+                None
+            } else {
+                let regex = lint.exceptions
+                    .iter()
+                    .map(|s| format!("(?:{})", s))
+                    .collect::<Vec<_>>()
+                    .join("|");
+                Some(RegexBuilder::new(&regex).unicode(unicode).build()?)
+            };
+
+            let conditional = match (&lint.first, &lint.second) {
+                (Some(first), Some(second)) => Some((
+                    RegexBuilder::new(first).unicode(unicode).build()?,
+                    RegexBuilder::new(second).unicode(unicode).build()?,
+                )),
+                _ if lint.kind == Kind::Conditional => {
+                    bail!("lint `{}` needs both a `first` and a `second` pattern", lint.name)
+                }
+                _ => None,
+            };
+
+            let dictionary = match lint.dictionary {
+                Some(ref path) if lint.kind == Kind::Spelling => {
+                    let mut dict = Dictionary::load(path)?;
+                    for list in &lint.accept {
+                        dict.accept(list)?;
+                    }
+                    Some(dict)
+                }
+                None if lint.kind == Kind::Spelling => {
+                    bail!("lint `{}` needs a `dictionary` to check spelling", lint.name)
+                }
+                _ => None,
+            };
+
+            let alternatives = lint.groups
+                .iter()
+                .map(|g| {
+                    g.iter()
+                        .map(|a| {
+                            RegexBuilder::new(&format!("^(?:{})$", a))
+                                .unicode(unicode)
+                                .build()
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            lints.push(CompiledLint {
+                lint,
+                tokens,
+                exceptions,
+                alternatives,
+                conditional,
+                dictionary,
+            });
+        }
+
+        let set = RegexSetBuilder::new(&patterns).unicode(unicode).build()?;
+
+        Ok(Linter {
+            set,
+            mapped,
+            newlines: nl,
+            reflowed: lints.iter().any(|cl| cl.lint.normalize_whitespace),
+            lints,
+        })
+    }
+}
+
+/// Whether `pattern` has any capture groups, which would be renumbered if it
+/// were joined with other patterns.
+fn has_captures(pattern: &str, unicode: bool) -> bool {
+    ParserBuilder::new()
+        .unicode(unicode)
+        .allow_invalid_utf8(!unicode)
+        .build()
+        .parse(pattern)
+        .is_ok_and(|hir| captures(&hir))
+}
+
+fn captures(hir: &Hir) -> bool {
+    match *hir.kind() {
+        HirKind::Group(ref g) => g.kind != GroupKind::NonCapturing || captures(&g.hir),
+        HirKind::Repetition(ref r) => captures(&r.hir),
+        HirKind::Concat(ref hs) | HirKind::Alternation(ref hs) => hs.iter().any(captures),
+        _ => false,
+    }
+}
+
+/// Finds the most line breaks that a match of `pattern` can contain, or `None`
+/// if there's no limit.
+fn max_newlines(pattern: &str, unicode: bool) -> Option<usize> {
+    ParserBuilder::new()
+        .unicode(unicode)
+        .allow_invalid_utf8(!unicode)
+        .build()
+        .parse(pattern)
+        .ok()
+        .and_then(|hir| newlines(&hir))
+}
+
+fn newlines(hir: &Hir) -> Option<usize> {
+    match *hir.kind() {
+        HirKind::Empty | HirKind::Anchor(_) | HirKind::WordBoundary(_) => Some(0),
+        HirKind::Literal(Literal::Unicode(c)) => Some((c == '\n') as usize),
+        HirKind::Literal(Literal::Byte(b)) => Some((b == b'\n') as usize),
+        HirKind::Class(Class::Unicode(ref c)) => Some(
+            c.iter().any(|r| r.start() <= '\n' && '\n' <= r.end()) as usize,
+        ),
+        HirKind::Class(Class::Bytes(ref c)) => Some(
+            c.iter().any(|r| r.start() <= b'\n' && b'\n' <= r.end()) as usize,
+        ),
+        HirKind::Repetition(ref r) => {
+            let n = newlines(&r.hir)?;
+            match r.kind {
+                _ if n == 0 => Some(0),
+                RepetitionKind::ZeroOrOne => Some(n),
+                RepetitionKind::Range(RepetitionRange::Exactly(m))
+                | RepetitionKind::Range(RepetitionRange::Bounded(_, m)) => Some(n * m as usize),
+                _ => None,
+            }
+        }
+        HirKind::Group(ref g) => newlines(&g.hir),
+        HirKind::Concat(ref hs) => hs.iter().map(newlines).sum(),
+        HirKind::Alternation(ref hs) => hs.iter()
+            .map(newlines)
+            .collect::<Option<Vec<_>>>()
+            .map(|ns| ns.into_iter().max().unwrap_or(0)),
+    }
+}
+
+fn regexes_per_partition(regexes: usize) -> usize {
+    let regexes = regexes as f64;
+    ((15000.0 / regexes) + (regexes / 10.0)).ceil() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ordermap::OrderMap;
+
+    fn lint(name: &str, mapping: &[(&str, Option<&str>)]) -> Lint {
+        let mut map = OrderMap::new();
+        for &(k, v) in mapping {
+            map.insert(String::from(k), v.map(String::from));
+        }
+
+        Lint {
+            name: String::from(name),
+            severity: Severity::Warning,
+            msg: String::from("{match}"),
+            msg_mapping: String::from("{match}: {value}"),
+            mapping: map,
+            ..Lint::default()
+        }
+    }
+
+    #[test]
+    fn linter_compile() {
+        let linter = Linter::new(
+            vec![
+                lint("a", &[("x", None), ("y", Some("z"))]),
+                lint("b", &[("w", Some("v"))]),
+            ],
+            true,
+        ).unwrap();
+
+        assert_eq!(2, linter.lints.len());
+        assert_eq!(1, linter.lints[0].tokens.len());
+        assert!(linter.lints[1].tokens.is_empty());
+        assert!(linter.lints[0].exceptions.is_none());
+        assert_eq!(2, linter.set.len());
+        assert_eq!(1, linter.mapped[1].lint);
+    }
+
+    #[test]
+    fn linter_newlines() {
+        let newlines = |p| Linter::new(vec![lint("a", &[(p, None)])], true)
+            .unwrap()
+            .newlines;
+
+        assert_eq!(Some(0), newlines(r"\bvery\b"));
+        assert_eq!(Some(2), newlines(r"in\s?order\s?to"));
+        assert_eq!(Some(5), newlines(r"(?:a\n|b\s){2,3}c\n?d\s"));
+        assert_eq!(None, newlines(r"in\s+order"));
+        assert_eq!(Some(0), newlines(r"[^\n]*"));
+
+        let reflowed = Lint {
+            normalize_whitespace: true,
+            ..lint("b", &[(r"in\s+order", None)])
+        };
+        let linter = Linter::new(vec![lint("a", &[("x", None)]), reflowed], true).unwrap();
+        assert_eq!(Some(0), linter.newlines);
+        assert!(linter.reflowed);
+    }
+
+    #[test]
+    fn linter_captures() {
+        let linter = Linter::new(
+            vec![lint("a", &[("x", None), ("(y)", None), ("(?P<n>z)", None), ("(?:w)", None)])],
+            true,
+        ).unwrap();
+
+        assert_eq!(3, linter.lints[0].tokens.len());
+        assert_eq!(2, linter.lints[0].tokens[1].captures_len());
+    }
+
+    #[test]
+    fn linter_compile_invalid() {
+        assert!(Linter::new(vec![lint("a", &[("(", None)])], true).is_err());
+    }
+
+    #[test]
+    fn linter_occurrence_bounds() {
+        let counted = |max| Lint {
+            kind: Kind::Occurrence,
+            max: Some(max),
+            ..lint("a", &[("a", None)])
+        };
+        assert!(Linter::new(vec![counted(2.0)], true).is_ok());
+        assert!(Linter::new(vec![counted(1.5)], true).is_err());
+        assert!(Linter::new(vec![counted(-1.0)], true).is_err());
+    }
+}
@@ -0,0 +1,587 @@
+//! Module `asciidoc.rs` finds the prose in AsciiDoc documents, skipping
+//! attribute entries, comments, listing, literal and passthrough blocks, tables
+//! and inline markup such as macro targets and attribute references.
+
+use std::str;
+
+use markup::*;
+
+/// The labels of admonition paragraphs, like `NOTE: text`.
+const ADMONITIONS: &[&str] = &["CAUTION", "IMPORTANT", "NOTE", "TIP", "WARNING"];
+
+/// The styles of blocks whose content isn't prose.
+const VERBATIM: &[&str] = &[
+    "asciimath",
+    "comment",
+    "latexmath",
+    "listing",
+    "literal",
+    "pass",
+    "source",
+    "stem",
+];
+
+/// Inline macros whose text is prose, as in `link:target[text]`.
+const TEXT_MACROS: &[&str] = &["footnote", "indexterm2", "link", "mailto", "xref"];
+
+/// Inline macros whose text isn't prose, as in `kbd:[Ctrl+C]`.
+const MACROS: &[&str] = &[
+    "anchor",
+    "asciimath",
+    "btn",
+    "footnoteref",
+    "icon",
+    "image",
+    "indexterm",
+    "kbd",
+    "latexmath",
+    "menu",
+    "pass",
+    "stem",
+];
+
+/// What the attribute list before a block, like `[NOTE]`, says about it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Style {
+    Prose(Block),
+    Verbatim,
+}
+
+pub fn regions(text: &str) -> Vec<Region> {
+    let mut sc = Scanner::new(text, inline_holes);
+
+    // The delimiter closing the block whose content we're skipping
+    let mut verbatim: Option<&str> = None;
+    // The delimiters of the blocks we're inside of, and the block of their
+    // content
+    let mut within: Vec<(&str, Block)> = Vec::new();
+    let mut style: Option<Style> = None;
+    // Whether we're skipping a literal paragraph, up to the next blank line
+    let mut literal = false;
+    let mut table: Option<&str> = None;
+    let mut list = false;
+    // Whether the line before was a list continuation, which attaches the next
+    // block to the list item before it
+    let mut attached = false;
+
+    let mut offset = 0;
+    for raw in text.split_inclusive('\n') {
+        let lstart = offset;
+        offset += raw.len();
+        let line = raw.trim_end();
+        let t = line.trim_start();
+        let tstart = lstart + (line.len() - t.len());
+        let tend = lstart + line.len();
+
+        if let Some(d) = verbatim {
+            if line == d {
+                verbatim = None;
+            }
+            continue;
+        }
+        if let Some(d) = table {
+            if line == d {
+                table = None;
+            }
+            continue;
+        }
+
+        if t.is_empty() {
+            sc.flush();
+            literal = false;
+            continue;
+        }
+        if literal {
+            continue;
+        }
+
+        if let Some(d) = delimiter(line) {
+            sc.flush();
+            list = false;
+            if within.last().is_some_and(|&(w, _)| w == d) {
+                within.pop();
+                continue;
+            }
+
+            let inherited = within.last().map_or(Block::Paragraph, |&(_, b)| b);
+            match (d.as_bytes()[0], style.take()) {
+                (_, Some(Style::Verbatim)) | (b'-', _) | (b'.', _) | (b'+', _) | (b'/', _)
+                | (b'`', _) => {
+                    verbatim = Some(if d.starts_with("```") { "```" } else { d });
+                }
+                (_, Some(Style::Prose(block))) => within.push((d, block)),
+                (b'_', None) => within.push((d, Block::BlockQuote)),
+                _ => within.push((d, inherited)),
+            }
+            continue;
+        }
+
+        if t == "+" {
+            // A list continuation
+            sc.flush();
+            attached = true;
+            continue;
+        }
+        if !attached && sc.pending.is_none() {
+            list = false;
+        }
+        attached = false;
+
+        let pending = sc.pending.as_ref().map(|p| p.block);
+        if pending.is_none() {
+            if t.starts_with("//") || t == "'''" || t == "<<<" || attribute_entry(t) {
+                continue;
+            }
+            let b = t.as_bytes();
+            if b.len() >= 4 && b[1..].starts_with(b"===") && b"|,:!".contains(&b[0]) {
+                table = Some(line);
+                continue;
+            }
+            if t.starts_with('[') && t.ends_with(']') {
+                // An attribute list or an anchor, which styles the next block
+                let first = t[1..t.len() - 1].split(',').next().unwrap_or("").trim();
+                let first = first.split(['.', '#', '%']).next().unwrap_or("");
+                if ADMONITIONS.contains(&first) {
+                    style = Some(Style::Prose(Block::Admonition));
+                } else if VERBATIM.contains(&first) {
+                    style = Some(Style::Verbatim);
+                } else if first == "quote" || first == "verse" {
+                    style = Some(Style::Prose(Block::BlockQuote));
+                }
+                continue;
+            }
+            if block_macro(t) {
+                continue;
+            }
+            if let Some(title) = t.strip_prefix('.') {
+                // A block title
+                if title.starts_with(|c: char| !c.is_whitespace() && c != '.') {
+                    sc.push(Block::Heading, tstart + 1, tend);
+                    sc.flush();
+                    continue;
+                }
+            }
+
+            let marks = t.bytes().take_while(|&b| b == b'=' || b == b'#').count();
+            if (1..=6).contains(&marks) && t[marks..].starts_with(' ') {
+                let h = t[marks..].trim_start();
+                // Strip the optional closing sequence of markers
+                let h = h.trim_end_matches(['=', '#']).trim_end();
+                if !h.is_empty() {
+                    let hs = tend - t[marks..].trim_start().len();
+                    sc.push(Block::Heading, hs, hs + h.len());
+                    sc.flush();
+                }
+                continue;
+            }
+
+            if style == Some(Style::Verbatim) || (line.starts_with([' ', '\t']) && !list) {
+                // A literal paragraph
+                style = None;
+                literal = true;
+                continue;
+            }
+        }
+
+        if pending.is_none() || pending == Some(Block::ListItem) {
+            if let Some(m) = list_marker(t) {
+                sc.flush();
+                list = true;
+                let item = t[m..].trim_start();
+                let s = tend - item.len();
+                let s = s + checkbox(item);
+                if s < tend {
+                    sc.push(Block::ListItem, s, tend);
+                }
+                continue;
+            }
+            if let Some((term, definition)) = description(t) {
+                sc.flush();
+                list = true;
+                sc.push(Block::ListItem, tstart, tstart + term);
+                if definition < t.len() {
+                    sc.push(Block::ListItem, tstart + definition, tend);
+                }
+                continue;
+            }
+        }
+
+        if pending.is_none() {
+            let label = ADMONITIONS
+                .iter()
+                .find(|l| t.starts_with(*l) && t[l.len()..].starts_with(": "));
+            if let Some(label) = label {
+                let rest = t[label.len() + 1..].trim_start();
+                sc.push(Block::Admonition, tend - rest.len(), tend);
+                continue;
+            }
+        }
+
+        let block = match (pending, style.take()) {
+            (Some(block), _) => block,
+            (None, Some(Style::Prose(block))) => block,
+            _ if list => Block::ListItem,
+            _ => within.last().map_or(Block::Paragraph, |&(_, b)| b),
+        };
+        sc.push(block, tstart, tend);
+    }
+
+    sc.flush();
+    sc.res
+}
+
+/// Returns the delimiter of the block that `line` opens or closes, if it's one.
+fn delimiter(line: &str) -> Option<&str> {
+    let b = line.as_bytes();
+    match b.first() {
+        Some(&b'`') if b.len() >= 3 && b[..3] == *b"```" => Some(line),
+        Some(&c) if b"-./+*=_".contains(&c) && b.len() >= 4 && b.iter().all(|&d| d == c) => {
+            Some(line)
+        }
+        _ if line == "--" => Some(line),
+        _ => None,
+    }
+}
+
+/// Whether `t` sets or unsets a document attribute, as in `:name: value`.
+fn attribute_entry(t: &str) -> bool {
+    let rest = match t.strip_prefix(':') {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    match rest.find(':') {
+        Some(i) if i > 0 => {
+            let name = rest[..i].trim_start_matches('!').trim_end_matches('!');
+            !name.is_empty()
+                && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+                && rest[i + 1..].chars().next().is_none_or(char::is_whitespace)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `t` is a block macro, like `image::target[alt]` or `include::x[]`.
+fn block_macro(t: &str) -> bool {
+    match t.find("::") {
+        Some(i) if i > 0 => {
+            let target = &t[i + 2..];
+            t[..i].bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+                && t.ends_with(']')
+                && target.find('[').is_some_and(|j| !target[..j].contains(' '))
+        }
+        _ => false,
+    }
+}
+
+/// Returns the length of the list marker at the start of `t`, if there is one.
+fn list_marker(t: &str) -> Option<usize> {
+    let b = t.as_bytes();
+    let m = match b.first() {
+        Some(&c) if c == b'*' || c == b'.' => run(b, c),
+        Some(&b'-') => 1,
+        Some(c) if c.is_ascii_digit() => {
+            let n = b.iter().take_while(|c| c.is_ascii_digit()).count();
+            if b.get(n) != Some(&b'.') {
+                return None;
+            }
+            n + 1
+        }
+        Some(c) if c.is_ascii_alphabetic() && b.get(1) == Some(&b'.') => 2,
+        _ => return None,
+    };
+
+    if m < b.len() && (b[m] == b' ' || b[m] == b'\t') {
+        Some(m)
+    } else {
+        None
+    }
+}
+
+/// Returns the length of the checkbox at the start of a list item, as in
+/// `[x] done`.
+fn checkbox(item: &str) -> usize {
+    let boxed = ["[ ] ", "[x] ", "[*] "].iter().any(|b| item.starts_with(b));
+    if boxed {
+        4
+    } else {
+        0
+    }
+}
+
+/// Splits an item of a description list, like `term:: definition`, returning
+/// where its term ends and its definition starts.
+fn description(t: &str) -> Option<(usize, usize)> {
+    let (i, n) = ["::::", ":::", "::", ";;"]
+        .iter()
+        .filter_map(|d| t.find(d).map(|i| (i, d.len())))
+        .min()?;
+    let after = &t[i + n..];
+
+    if i == 0 || !after.chars().next().is_none_or(char::is_whitespace) {
+        return None;
+    }
+    Some((t[..i].trim_end().len(), t.len() - after.trim_start().len()))
+}
+
+/// Finds the inline markup in a block made up of `ranges`, returning the
+/// ranges of bytes which aren't prose: monospace text, passthroughs, attribute
+/// references, anchors, cross references, urls and the targets of macros.
+fn inline_holes(text: &str, ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let (b, pos) = joined(text, ranges);
+    let n = b.len();
+
+    let mut holes: Vec<(usize, usize)> = Vec::new();
+    let hole = |i: usize, j: usize, holes: &mut Vec<(usize, usize)>| {
+        if i < j {
+            holes.push((pos[i], pos[j - 1] + 1));
+        }
+    };
+    let closing = |from: usize, c: u8| b[from..].iter().position(|&d| d == c).map(|j| from + j);
+
+    let mut i = 0;
+    while i < n {
+        // Inline markup can't start in the middle of a word
+        let start = i == 0 || !b[i - 1].is_ascii_alphanumeric();
+
+        match b[i] {
+            b'\\' if i + 1 < n && b[i + 1].is_ascii_punctuation() => {
+                hole(i, i + 1, &mut holes);
+                i += 2;
+            }
+            c @ b'`' | c @ b'+' if start => {
+                let r = run(&b[i..], c).min(3);
+                let close = find(&b, i + r, &b[i..i + r])
+                    .filter(|&j| j > i + r && !b[i + r].is_ascii_whitespace());
+                match close {
+                    Some(j) => {
+                        hole(i, j + r, &mut holes);
+                        i = j + r;
+                    }
+                    None => i += r,
+                }
+            }
+            b'{' => {
+                let close = closing(i + 1, b'}').filter(|&j| {
+                    j > i + 1
+                        && b[i + 1..j]
+                            .iter()
+                            .all(|c| c.is_ascii_alphanumeric() || *c == b'_' || *c == b'-')
+                });
+                match close {
+                    Some(j) => {
+                        hole(i, j + 1, &mut holes);
+                        i = j + 1;
+                    }
+                    None => i += 1,
+                }
+            }
+            b'<' if b[i..].starts_with(b"<<") => match find(&b, i + 2, b">>") {
+                Some(j) => {
+                    // Only the text of a cross reference is prose, as in
+                    // <<id,the text>>
+                    match b[i + 2..j].iter().position(|&c| c == b',') {
+                        Some(k) => {
+                            hole(i, i + 3 + k, &mut holes);
+                            hole(j, j + 2, &mut holes);
+                        }
+                        None => hole(i, j + 2, &mut holes),
+                    }
+                    i = j + 2;
+                }
+                None => i += 2,
+            },
+            b'[' if b[i..].starts_with(b"[[") => match find(&b, i + 2, b"]]") {
+                Some(j) => {
+                    let to = j + 2 + (b.get(j + 2) == Some(&b']')) as usize;
+                    hole(i, to, &mut holes);
+                    i = to;
+                }
+                None => i += 2,
+            },
+            c if start && c.is_ascii_alphabetic() => {
+                let w = b[i..].iter().take_while(|c| c.is_ascii_alphanumeric()).count();
+                let name = str::from_utf8(&b[i..i + w]).unwrap_or("");
+                let target_end = |from: usize| {
+                    b[from..]
+                        .iter()
+                        .position(|&c| c == b'[' || c.is_ascii_whitespace())
+                        .map(|j| from + j)
+                        .filter(|&j| b[j] == b'[')
+                };
+
+                if let Some(e) = url(&b, i) {
+                    // A url, whose text in brackets is prose
+                    let bracket = b[i..e].iter().position(|&c| c == b'[').map(|k| i + k);
+                    match bracket.and_then(|k| closing(k, b']').map(|j| (k, j))) {
+                        Some((k, j)) => {
+                            hole(i, k + 1, &mut holes);
+                            hole(j, j + 1, &mut holes);
+                            i = j + 1;
+                        }
+                        None => {
+                            hole(i, e, &mut holes);
+                            i = e;
+                        }
+                    }
+                    continue;
+                }
+
+                let known = TEXT_MACROS.contains(&name) || MACROS.contains(&name);
+                let open = if known && b.get(i + w) == Some(&b':') {
+                    target_end(i + w + 1)
+                } else {
+                    None
+                };
+                match open.and_then(|k| closing(k, b']').map(|j| (k, j))) {
+                    Some((k, j)) => {
+                        if TEXT_MACROS.contains(&name) {
+                            hole(i, k + 1, &mut holes);
+                            hole(j, j + 1, &mut holes);
+                        } else {
+                            hole(i, j + 1, &mut holes);
+                        }
+                        i = j + 1;
+                    }
+                    None => i += w,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    holes.sort();
+    holes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prose(text: &str) -> String {
+        mask(text, &regions(text))
+    }
+
+    fn kept(text: &str) -> Vec<&str> {
+        regions(text)
+            .iter()
+            .map(|r| &text[r.start..r.end])
+            .collect()
+    }
+
+    #[test]
+    fn asciidoc_skipped() {
+        let text = "\
+= Manual
+:very: attribute
+:toc:
+
+// a very comment
+
+[source,rust]
+----
+let very = 1;
+----
+
+++++
+<b>very</b>
+++++
+
+////
+very
+////
+
+ very literal
+
+image::very.png[Very]
+
+|===
+| very | cell
+|===
+
+Done.
+";
+        assert_eq!(vec!["Manual", "Done."], kept(text));
+    }
+
+    #[test]
+    fn asciidoc_blocks() {
+        let text = "\
+== Section
+
+.A title
+Some text
+here.
+
+NOTE: Be careful.
+
+[WARNING]
+====
+Really.
+====
+
+* item one
+continued
+* [x] item two
+
+Term:: definition
++
+More.
+
+After.
+
+____
+quoted
+____
+";
+        let blocks: Vec<_> = regions(text)
+            .iter()
+            .map(|r| (&text[r.start..r.end], r.block))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("Section", Block::Heading),
+                ("A title", Block::Heading),
+                ("Some text", Block::Paragraph),
+                ("here.", Block::Paragraph),
+                ("Be careful.", Block::Admonition),
+                ("Really.", Block::Admonition),
+                ("item one", Block::ListItem),
+                ("continued", Block::ListItem),
+                ("item two", Block::ListItem),
+                ("Term", Block::ListItem),
+                ("definition", Block::ListItem),
+                ("More.", Block::ListItem),
+                ("After.", Block::Paragraph),
+                ("quoted", Block::BlockQuote),
+            ],
+            blocks
+        );
+    }
+
+    #[test]
+    fn asciidoc_inline() {
+        let text = "Use `very`, {very}, <<very,the docs>>, link:very.html[a link], \
+                    https://very.com[site] and kbd:[Ctrl+V].\n";
+        assert_eq!(
+            "Use       ,       ,        the docs  ,                a link , \
+             \x20                site  and             .\n",
+            prose(text)
+        );
+    }
+
+    #[test]
+    fn asciidoc_offsets() {
+        let text = "= A\n\n`x` very\n";
+        let masked = prose(text);
+        assert_eq!(text.len(), masked.len());
+        assert_eq!(text.find("very"), masked.find("very"));
+    }
+
+    #[test]
+    fn asciidoc_non_ascii() {
+        let text = "“Quoted” text\n\n|===\n| “cell” very\n|===\n\n* “Item” here\n";
+        assert_eq!(vec!["“Quoted” text", "“Item” here"], kept(text));
+    }
+}
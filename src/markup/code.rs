@@ -0,0 +1,586 @@
+//! Module `code.rs` finds the prose in source code: line and block comments,
+//! doc comments and Python docstrings. Code, including every other string
+//! literal and shell here-documents, is skipped, as are code spans, urls and
+//! example code inside of comments.
+
+use failure::Error;
+use std::str::FromStr;
+
+use markup::*;
+
+/// A programming language whose comments can be linted.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Rust,
+    Python,
+    Go,
+    /// JavaScript and TypeScript.
+    JavaScript,
+    /// C and C++.
+    C,
+    Shell,
+}
+
+impl Language {
+    /// Returns the language of files with the lowercase extension `ext`.
+    pub fn from_extension(ext: &str) -> Option<Language> {
+        match ext {
+            "rs" => Some(Language::Rust),
+            "py" | "pyi" => Some(Language::Python),
+            "go" => Some(Language::Go),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" => Some(Language::JavaScript),
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Some(Language::C),
+            "sh" | "bash" | "zsh" => Some(Language::Shell),
+            _ => None,
+        }
+    }
+
+    /// Whether comments start with `#` rather than `//` and `/*`.
+    fn hashes(self) -> bool {
+        self == Language::Python || self == Language::Shell
+    }
+}
+
+impl FromStr for Language {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "rust" => Ok(Language::Rust),
+            "python" => Ok(Language::Python),
+            "go" => Ok(Language::Go),
+            "javascript" => Ok(Language::JavaScript),
+            "c" => Ok(Language::C),
+            "shell" => Ok(Language::Shell),
+            _ => Err(format_err!("unknown language `{}`", s)),
+        }
+    }
+}
+
+pub fn regions(text: &str, language: Language) -> Vec<Region> {
+    let b = text.as_bytes();
+    let n = b.len();
+    let mut comments = Comments {
+        sc: Scanner::new(text, inline_holes),
+        last: None,
+        fenced: false,
+        doctest: false,
+    };
+
+    let mut i = 0;
+    if language.hashes() && b.starts_with(b"#!") {
+        // A shebang
+        i = line_end(b, 0);
+    }
+    // Whether a string starting here would be a Python docstring, which is
+    // the first statement of a module, class or function
+    let mut doc = true;
+    // The shell here-documents whose bodies start after this line
+    let mut heredocs = Vec::new();
+
+    while i < n {
+        let c = b[i];
+
+        if c == b'\n' && !heredocs.is_empty() {
+            i = heredocs_end(b, i + 1, &heredocs);
+            heredocs.clear();
+            continue;
+        }
+        if language == Language::Shell && c == b'<' {
+            if let Some((word, strip, end)) = heredoc(b, i) {
+                heredocs.push((word, strip));
+                i = end;
+                continue;
+            }
+        }
+
+        let hash = c == b'#'
+            && (language == Language::Python
+                || (language == Language::Shell
+                    && (i == 0 || b" \t\n;|&(".contains(&b[i - 1]))));
+        if hash {
+            let e = line_end(b, i);
+            comments.line(i, i + run(&b[i..], b'#'), e);
+            i = e;
+            continue;
+        }
+        if !language.hashes() && b[i..].starts_with(b"//") {
+            let e = line_end(b, i);
+            // Doc comments start with `///` or `//!`
+            let m = i + run(&b[i..], b'/');
+            let m = m + (b.get(m) == Some(&b'!')) as usize;
+            comments.line(i, m.min(e), e);
+            i = e;
+            continue;
+        }
+        if !language.hashes() && b[i..].starts_with(b"/*") {
+            let (s, e, end) = block_comment(b, i, language == Language::Rust);
+            comments.block(s, e, true);
+            i = end;
+            continue;
+        }
+
+        let string = match (language, c) {
+            (Language::Rust, b'\'') => {
+                i = char_end(b, i);
+                continue;
+            }
+            (Language::Rust, b'"') => match raw_hashes(b, i) {
+                Some(h) => {
+                    let close = [b"\"".as_ref(), &b"#".repeat(h)].concat();
+                    Some((1, string_end(b, i + 1, &close, false, true)))
+                }
+                None => Some((1, string_end(b, i + 1, b"\"", true, true))),
+            },
+            (Language::Python, b'"') | (Language::Python, b'\'') => {
+                let q = if b[i..].starts_with(&[c; 3]) { 3 } else { 1 };
+                Some((q, string_end(b, i + q, &b[i..i + q], true, q == 3)))
+            }
+            (Language::Go, b'`') => Some((1, string_end(b, i + 1, b"`", false, true))),
+            (Language::JavaScript, b'`') => Some((1, string_end(b, i + 1, b"`", true, true))),
+            (Language::Shell, b'\'') => Some((1, string_end(b, i + 1, b"'", false, true))),
+            (Language::Shell, b'"') => Some((1, string_end(b, i + 1, b"\"", true, true))),
+            (_, b'"') | (_, b'\'') if language != Language::Shell => {
+                Some((1, string_end(b, i + 1, &b[i..i + 1], true, false)))
+            }
+            _ => None,
+        };
+
+        if let Some((q, end)) = string {
+            if language == Language::Python && doc && statement(b, i) {
+                let close = if end - i >= 2 * q && b[end - q..end] == b[i..i + q] {
+                    end - q
+                } else {
+                    end
+                };
+                comments.block(i + q, close, false);
+            }
+            doc = false;
+            i = end;
+            continue;
+        }
+
+        if language == Language::Python && c.is_ascii_alphabetic() {
+            // The prefix of a string, as in `r"""`, doesn't end the chance of a
+            // docstring
+            let w = b[i..].iter().take_while(|c| c.is_ascii_alphanumeric()).count();
+            let prefix = w <= 2 && b[i..i + w].iter().all(|c| b"rRbBuUfF".contains(c));
+            if !(prefix && b.get(i + w).is_some_and(|&q| q == b'"' || q == b'\'')) {
+                doc = false;
+            }
+            i += w;
+            continue;
+        }
+
+        if c == b':' {
+            doc = true;
+        } else if !c.is_ascii_whitespace() {
+            doc = false;
+        }
+        i += 1;
+    }
+
+    comments.sc.flush();
+    comments.sc.res
+}
+
+/// Collects the lines of comments into paragraphs.
+struct Comments<'a> {
+    sc: Scanner<'a>,
+    /// Where the last line comment ended, which the next one may continue.
+    last: Option<usize>,
+    /// Whether we're skipping a fenced code block in a comment.
+    fenced: bool,
+    /// Whether we're skipping a Python doctest, up to the next blank line.
+    doctest: bool,
+}
+
+impl<'a> Comments<'a> {
+    /// Adds a line comment starting at `at`, whose text lies between `start`
+    /// and `end`. Line comments on consecutive lines make up one block.
+    fn line(&mut self, at: usize, start: usize, end: usize) {
+        let text = self.sc.text;
+        let follows = self.last.is_some_and(|l| {
+            let gap = &text[l..at];
+            gap.trim().is_empty() && gap.matches('\n').count() == 1
+        });
+        if !follows {
+            self.reset();
+        }
+
+        self.content(start, end, false);
+        self.last = Some(end);
+    }
+
+    /// Adds a block comment or docstring whose text lies between `start` and
+    /// `end`. With `decorated`, each of its lines may start with a `*`.
+    fn block(&mut self, start: usize, end: usize, decorated: bool) {
+        self.reset();
+
+        let mut s = start;
+        for line in self.sc.text[start..end].split_inclusive('\n') {
+            self.content(s, s + line.len(), decorated);
+            s += line.len();
+        }
+
+        self.reset();
+        self.last = None;
+    }
+
+    /// Adds a line of text from a comment. Blank lines end paragraphs.
+    fn content(&mut self, start: usize, end: usize, decorated: bool) {
+        let mut t = self.sc.text[start..end].trim_start();
+        if decorated {
+            // The `*`s framing the lines of a block comment
+            t = t.trim_start_matches('*').trim_start();
+        }
+        let s = end - t.len();
+        let mut t = t.trim_end();
+        if decorated {
+            t = t.trim_end_matches('*').trim_end();
+        }
+
+        if t.is_empty() {
+            self.sc.flush();
+            self.doctest = false;
+            return;
+        }
+        if t.starts_with("```") || t.starts_with("~~~") {
+            // A fenced code block, as in a Rust doc comment
+            self.sc.flush();
+            self.fenced = !self.fenced;
+            return;
+        }
+        if self.fenced || self.doctest {
+            return;
+        }
+        if t.starts_with(">>>") || t.starts_with("temper-") || t.starts_with("-*-") {
+            // A doctest, a suppression or an editor setting
+            self.sc.flush();
+            self.doctest = t.starts_with(">>>");
+            return;
+        }
+
+        self.sc.push(Block::Paragraph, s, s + t.len());
+    }
+
+    fn reset(&mut self) {
+        self.sc.flush();
+        self.fenced = false;
+        self.doctest = false;
+    }
+}
+
+/// Returns the delimiting word of the shell here-document whose `<<` is at
+/// `i`, whether its closing line may be indented with tabs, as with `<<-`,
+/// and the position after the word.
+fn heredoc(b: &[u8], i: usize) -> Option<(&[u8], bool, usize)> {
+    // `<<<` is a here-string, which is only one word
+    let string = b.get(i + 2) == Some(&b'<') || (i > 0 && b[i - 1] == b'<');
+    if !b[i..].starts_with(b"<<") || string {
+        return None;
+    }
+    let strip = b.get(i + 2) == Some(&b'-');
+    let j = i + 2 + strip as usize;
+    let j = j + b[j..].iter().take_while(|&&c| c == b' ' || c == b'\t').count();
+
+    let (word, end) = match b.get(j) {
+        Some(&q) if q == b'\'' || q == b'"' => {
+            let len = b[j + 1..].iter().position(|&c| c == q || c == b'\n')?;
+            if b[j + 1 + len] != q {
+                return None;
+            }
+            (&b[j + 1..j + 1 + len], j + len + 2)
+        }
+        _ => {
+            let s = j + (b.get(j) == Some(&b'\\')) as usize;
+            let len = b[s..]
+                .iter()
+                .take_while(|c| !c.is_ascii_whitespace() && !b";|&<>()".contains(c))
+                .count();
+            (&b[s..s + len], s + len)
+        }
+    };
+
+    // Shifts, as in `$((1 << 2))`, aren't here-documents
+    if word.is_empty() || word[0].is_ascii_digit() {
+        return None;
+    }
+    Some((word, strip, end))
+}
+
+/// Returns the position after the bodies of the here-documents `docs`, which
+/// follow each other from `from`. Each ends with a line holding only its word.
+fn heredocs_end(b: &[u8], from: usize, docs: &[(&[u8], bool)]) -> usize {
+    let mut i = from;
+    for &(word, strip) in docs {
+        while i < b.len() {
+            let e = line_end(b, i);
+            let line = &b[i..e];
+            let line = if strip { &line[run(line, b'\t')..] } else { line };
+            i = e + 1;
+            if line == word {
+                break;
+            }
+        }
+    }
+    i.min(b.len())
+}
+
+/// Returns the position of the end of the line that `i` is on.
+fn line_end(b: &[u8], i: usize) -> usize {
+    b[i..].iter().position(|&c| c == b'\n').map_or(b.len(), |j| i + j)
+}
+
+/// Returns where the text of the block comment at `i` starts and ends, along
+/// with the position after it. Rust's block comments nest.
+fn block_comment(b: &[u8], i: usize, nested: bool) -> (usize, usize, usize) {
+    let s = i + 2 + run(&b[i + 2..], b'*');
+    let s = s + (b.get(s) == Some(&b'!')) as usize;
+
+    let mut depth = 0;
+    let mut j = i;
+    while j < b.len() {
+        if b[j..].starts_with(b"/*") && (nested || depth == 0) {
+            depth += 1;
+            j += 2;
+        } else if b[j..].starts_with(b"*/") {
+            depth -= 1;
+            j += 2;
+            if depth == 0 {
+                return (s.min(j - 2), j - 2, j);
+            }
+        } else {
+            j += 1;
+        }
+    }
+    (s.min(b.len()), b.len(), b.len())
+}
+
+/// Returns the position after the string whose content starts at `from`, and
+/// which is closed by `close`. Strings which aren't `multiline` end at the end
+/// of their line.
+fn string_end(b: &[u8], from: usize, close: &[u8], escapes: bool, multiline: bool) -> usize {
+    let mut j = from;
+    while j < b.len() {
+        if escapes && b[j] == b'\\' {
+            j += 2;
+        } else if b[j..].starts_with(close) {
+            return j + close.len();
+        } else if !multiline && b[j] == b'\n' {
+            return j;
+        } else {
+            j += 1;
+        }
+    }
+    b.len()
+}
+
+/// Returns the number of `#`s of the raw Rust string whose opening quote is at
+/// `i`, as in `r#"`, if it's a raw string.
+fn raw_hashes(b: &[u8], i: usize) -> Option<usize> {
+    let h = b[..i].iter().rev().take_while(|&&c| c == b'#').count();
+    let r = i.checked_sub(h + 1)?;
+    let ident = |k: usize| b[k].is_ascii_alphanumeric() || b[k] == b'_';
+
+    let prefixed = r == 0
+        || !ident(r - 1)
+        || (b"bc".contains(&b[r - 1]) && (r == 1 || !ident(r - 2)));
+    if b[r] == b'r' && prefixed {
+        Some(h)
+    } else {
+        None
+    }
+}
+
+/// Returns the position after the Rust char literal or lifetime at `i`.
+fn char_end(b: &[u8], i: usize) -> usize {
+    if b.get(i + 1) == Some(&b'\\') {
+        return string_end(b, i + 2, b"'", true, false);
+    }
+
+    let w = b.get(i + 1).map_or(1, |&c| width(c));
+    if b.get(i + 1 + w) == Some(&b'\'') {
+        i + 2 + w
+    } else {
+        i + 1
+    }
+}
+
+/// Whether the Python string at `i` starts a statement, with nothing but
+/// indentation and its prefix before it on its line.
+fn statement(b: &[u8], i: usize) -> bool {
+    let start = b[..i].iter().rposition(|&c| c == b'\n').map_or(0, |j| j + 1);
+    let before = &b[start..i];
+    let prefix = before.iter().rev().take_while(|c| c.is_ascii_alphabetic()).count();
+    before[..before.len() - prefix].iter().all(|&c| c == b' ' || c == b'\t')
+}
+
+/// Finds the inline markup in a block of comments made up of `ranges`,
+/// returning the ranges of bytes which aren't prose: code spans, urls and the
+/// tags of doc comments, like `@param`.
+fn inline_holes(text: &str, ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let (b, pos) = joined(text, ranges);
+    let n = b.len();
+
+    let mut holes: Vec<(usize, usize)> = Vec::new();
+    let hole = |i: usize, j: usize, holes: &mut Vec<(usize, usize)>| {
+        if i < j {
+            holes.push((pos[i], pos[j - 1] + 1));
+        }
+    };
+
+    let mut i = 0;
+    while i < n {
+        let start = i == 0 || !b[i - 1].is_ascii_alphanumeric();
+
+        match b[i] {
+            b'`' => {
+                let r = run(&b[i..], b'`');
+                match find(&b, i + r, &b[i..i + r]) {
+                    Some(j) => {
+                        hole(i, j + r, &mut holes);
+                        i = j + r;
+                    }
+                    None => i += r,
+                }
+            }
+            b'@' if start && b.get(i + 1).is_some_and(u8::is_ascii_alphabetic) => {
+                let w = b[i + 1..].iter().take_while(|c| c.is_ascii_alphanumeric()).count();
+                hole(i, i + 1 + w, &mut holes);
+                i += 1 + w;
+            }
+            c if start && c.is_ascii_alphabetic() => match url(&b, i) {
+                Some(e) => {
+                    hole(i, e, &mut holes);
+                    i = e;
+                }
+                None => i += b[i..].iter().take_while(|c| c.is_ascii_alphanumeric()).count(),
+            },
+            _ => i += 1,
+        }
+    }
+
+    holes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kept(text: &str, language: Language) -> Vec<&str> {
+        regions(text, language)
+            .iter()
+            .map(|r| &text[r.start..r.end])
+            .collect()
+    }
+
+    fn groups(text: &str, language: Language) -> Vec<usize> {
+        regions(text, language).iter().map(|r| r.group).collect()
+    }
+
+    #[test]
+    fn code_rust() {
+        let text = "\
+//! The crate.
+
+/// Adds one,
+/// quickly.
+///
+/// ```
+/// // very
+/// ```
+fn f<'a>(s: &'a str) -> char {
+    let url = \"http://very.com\"; // A note.
+    let raw = r#\"very \" // very\"#;
+    /* A block
+     * comment. /* nested */ still
+     */
+    '\"'
+}
+";
+        assert_eq!(
+            vec![
+                "The crate.",
+                "Adds one,",
+                "quickly.",
+                "A note.",
+                "A block",
+                "comment. /* nested */ still",
+            ],
+            kept(text, Language::Rust)
+        );
+        assert_eq!(vec![0, 1, 1, 2, 3, 3], groups(text, Language::Rust));
+    }
+
+    #[test]
+    fn code_python() {
+        let text = "\
+#!/usr/bin/env python
+# -*- coding: utf-8 -*-
+\"\"\"The module.\"\"\"
+
+x = \"# very\"  # A note.
+y = {'a': \"very\"}
+
+def f(a: int) -> int:
+    r'''
+    Does things.
+
+    >>> f(1)
+    very
+    '''
+    \"very\"
+    return a
+";
+        assert_eq!(
+            vec!["The module.", "A note.", "Does things."],
+            kept(text, Language::Python)
+        );
+    }
+
+    #[test]
+    fn code_others() {
+        let go = "s := `very // very` // A note.\n";
+        assert_eq!(vec!["A note."], kept(go, Language::Go));
+
+        let js = "/**\n * Adds.\n * @param a the number\n */\nconst s = `very ${a}`;\n";
+        assert_eq!(vec!["Adds.", " a the number"], kept(js, Language::JavaScript));
+
+        let c = "char *s = \"/* very */\"; /* A note. */\n";
+        assert_eq!(vec!["A note."], kept(c, Language::C));
+
+        let sh = "#!/bin/sh\necho \"# very\" $# ${#a} # A note.\n# temper-disable-line\n";
+        assert_eq!(vec!["A note."], kept(sh, Language::Shell));
+    }
+
+    #[test]
+    fn code_shell_heredocs() {
+        let sh = "\
+cat <<EOF # One.
+# very
+EOF
+cat <<-'END' <<\\TWO
+\t# very
+\tEND
+# very
+TWO
+x=$((1 << 2)) # Two.
+cat <<< \"# very\"
+# Three.
+";
+        assert_eq!(vec!["One.", "Two.", "Three."], kept(sh, Language::Shell));
+    }
+
+    #[test]
+    fn code_offsets() {
+        let text = "fn f() {} // Use `very` or very, see https://very.com.\n";
+        let masked = mask(text, &regions(text, Language::Rust));
+        assert_eq!(text.len(), masked.len());
+        assert_eq!(
+            "             Use        or very, see                 .\n",
+            masked
+        );
+    }
+}
@@ -0,0 +1,441 @@
+//! Module `html.rs` finds the prose in HTML and XML documents, such as rendered
+//! help pages and DocBook sources: their text nodes and the values of a few
+//! attributes, like `alt` and `title`. Tags, comments, entities and the content
+//! of elements like `<script>` and `<code>` are skipped.
+
+use memchr::memchr;
+use serde::{Deserialize, Deserializer};
+
+use markup::*;
+
+/// Elements which hold phrases rather than blocks of text, and don't break up
+/// the paragraph around them.
+const INLINE: &[&str] = &[
+    "a", "abbr", "acronym", "application", "b", "bdi", "bdo", "big", "br", "cite", "citetitle",
+    "code", "command", "computeroutput", "data", "del", "dfn", "em", "emphasis", "envar",
+    "filename", "firstterm", "font", "foreignphrase", "function", "glossterm", "guibutton",
+    "guilabel", "guimenu", "guimenuitem", "i", "img", "ins", "kbd", "keycap", "label", "link",
+    "literal", "mark", "olink", "option", "parameter", "phrase", "productname", "q", "quote",
+    "replaceable", "s", "samp", "small", "span", "strong", "sub", "subscript", "sup",
+    "superscript", "time", "trademark", "tt", "u", "ulink", "userinput", "var", "varname",
+    "wbr", "wordasword", "xref",
+];
+
+/// HTML elements which never have any content or end tag.
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param",
+    "source", "track", "wbr",
+];
+
+/// HTML elements whose content is raw text, which can't hold any tags.
+const RAW: &[&str] = &["script", "style", "textarea", "title"];
+
+/// HTML elements which are closed by another of their kind, like `<li>`.
+const SELF_NESTING: &[&str] = &["dd", "dt", "li", "option", "p", "td", "th", "tr"];
+
+/// Elements whose content isn't linted by default.
+const SKIP: &[&str] = &[
+    "script", "style", "code", "pre", "kbd", "samp", "var", "tt", "math", "svg", "template",
+    "programlisting", "screen", "literallayout", "synopsis", "literal", "computeroutput",
+    "userinput", "filename", "command", "envar", "varname", "function", "parameter",
+    "replaceable",
+];
+
+/// Attributes whose values are linted by default.
+const ATTRIBUTES: &[&str] = &["alt", "title"];
+
+/// Which parts of an HTML or XML document are linted. The lists read from a
+/// config file are added to the defaults rather than replacing them, so that
+/// skipping one more element doesn't start linting scripts.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Options {
+    /// Elements whose content is never linted.
+    #[serde(deserialize_with = "extra_skip")]
+    pub skip: Vec<String>,
+    /// Attributes whose values are linted.
+    #[serde(deserialize_with = "extra_attributes")]
+    pub attributes: Vec<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            skip: SKIP.iter().map(|s| String::from(*s)).collect(),
+            attributes: ATTRIBUTES.iter().map(|s| String::from(*s)).collect(),
+        }
+    }
+}
+
+fn extra_skip<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<String>, D::Error> {
+    extended(SKIP, d)
+}
+
+fn extra_attributes<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<String>, D::Error> {
+    extended(ATTRIBUTES, d)
+}
+
+/// Reads a list of names, and adds the ones which are new to `defaults`.
+fn extended<'de, D>(defaults: &[&str], d: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut names: Vec<String> = defaults.iter().map(|s| String::from(*s)).collect();
+    for name in Vec::<String>::deserialize(d)? {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+/// The kind of block that the content of an element belongs to, if it decides
+/// one.
+fn block(name: &str) -> Option<Block> {
+    match name {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "title" | "subtitle" | "titleabbrev" => {
+            Some(Block::Heading)
+        }
+        "li" | "dt" | "dd" | "listitem" | "term" => Some(Block::ListItem),
+        "blockquote" | "epigraph" => Some(Block::BlockQuote),
+        "caution" | "important" | "note" | "tip" | "warning" => Some(Block::Admonition),
+        _ => None,
+    }
+}
+
+/// Finds the prose in `text`, which is HTML or, if `xml` is set, XML. Element
+/// and attribute names are matched case insensitively in HTML.
+pub fn regions(text: &str, options: &Options, xml: bool) -> Vec<Region> {
+    let b = text.as_bytes();
+    let mut sc = Scanner::new(text, entities);
+    // The names of the elements we're inside of
+    let mut stack: Vec<String> = Vec::new();
+    let name = |s: &str| if xml { String::from(s) } else { s.to_lowercase() };
+
+    let mut i = 0;
+    let mut start = 0;
+    while i < b.len() {
+        let lt = match memchr(b'<', &b[i..]) {
+            Some(j) => i + j,
+            None => b.len(),
+        };
+        let markup = b.get(lt + 1).is_some_and(|&c| {
+            c.is_ascii_alphabetic() || c == b'/' || c == b'!' || c == b'?'
+        });
+        if lt < b.len() && !markup {
+            // A stray `<`, which is just text
+            i = lt + 1;
+            continue;
+        }
+
+        // The text before the markup
+        let t = &text[start..lt];
+        let s = start + (t.len() - t.trim_start().len());
+        let e = start + t.trim_end().len();
+        if s < e {
+            let block = stack
+                .iter()
+                .rev()
+                .find_map(|n| block(n))
+                .unwrap_or(Block::Paragraph);
+            if sc.pending.as_ref().is_some_and(|p| p.block != block) {
+                sc.flush();
+            }
+            sc.push(block, s, e);
+        }
+        if lt == b.len() {
+            break;
+        }
+
+        let rest = &b[lt..];
+        let end = if rest.starts_with(b"<!--") {
+            find(b, lt + 4, b"-->").map_or(b.len(), |j| j + 3)
+        } else if rest.starts_with(b"<![CDATA[") {
+            find(b, lt + 9, b"]]>").map_or(b.len(), |j| j + 3)
+        } else if rest.starts_with(b"<?") {
+            find(b, lt + 2, b"?>").map_or(b.len(), |j| j + 2)
+        } else if rest.starts_with(b"<!") {
+            declaration(b, lt)
+        } else if rest.starts_with(b"</") {
+            let tag = tag(text, lt + 2);
+            let n = name(tag.name);
+            if !INLINE.contains(&&n[..]) {
+                sc.flush();
+            }
+            if let Some(k) = stack.iter().rposition(|s| *s == n) {
+                stack.truncate(k);
+            }
+            tag.end
+        } else {
+            let tag = tag(text, lt + 1);
+            let n = name(tag.name);
+            let inline = INLINE.contains(&&n[..]);
+            if !inline {
+                sc.flush();
+            }
+
+            for &(a, s, e) in &tag.attributes {
+                let a = name(a);
+                if options.attributes.iter().any(|x| name(x) == a) && s < e {
+                    sc.flush();
+                    sc.push(Block::Paragraph, s, e);
+                    sc.flush();
+                }
+            }
+
+            let void = tag.closed || (!xml && VOID.contains(&&n[..]));
+            if !xml && SELF_NESTING.contains(&&n[..]) && stack.last() == Some(&n) {
+                stack.pop();
+            }
+
+            if void {
+                tag.end
+            } else if options.skip.iter().any(|x| name(x) == n) {
+                closing(text, tag.end, &n, xml || !RAW.contains(&&n[..]), xml)
+            } else {
+                stack.push(n);
+                tag.end
+            }
+        };
+
+        i = end;
+        start = end;
+    }
+
+    sc.flush();
+    sc.res
+}
+
+/// A start or end tag.
+struct Tag<'a> {
+    name: &'a str,
+    /// The names of the tag's attributes, and the ranges of their values.
+    attributes: Vec<(&'a str, usize, usize)>,
+    /// Whether the tag closes itself, like `<br/>`.
+    closed: bool,
+    /// Where the tag ends, after its `>`.
+    end: usize,
+}
+
+/// Reads the tag whose name starts at `i`.
+fn tag(text: &str, i: usize) -> Tag<'_> {
+    let b = text.as_bytes();
+    let word = |mut j: usize| {
+        while j < b.len() && !b[j].is_ascii_whitespace() && !b"/>=".contains(&b[j]) {
+            j += 1;
+        }
+        j
+    };
+    let spaces = |mut j: usize| {
+        while j < b.len() && b[j].is_ascii_whitespace() {
+            j += 1;
+        }
+        j
+    };
+
+    let n = word(i);
+    let mut tag = Tag {
+        name: &text[i..n],
+        attributes: Vec::new(),
+        closed: false,
+        end: b.len(),
+    };
+
+    let mut j = n;
+    while j < b.len() {
+        j = spaces(j);
+        match b.get(j) {
+            Some(&b'>') => {
+                tag.end = j + 1;
+                break;
+            }
+            Some(&b'/') => {
+                tag.closed = b.get(j + 1) == Some(&b'>');
+                j += 1;
+            }
+            Some(_) => {
+                let k = word(j);
+                let attr = &text[j..k];
+                j = spaces(k);
+                if b.get(j) != Some(&b'=') {
+                    continue;
+                }
+                j = spaces(j + 1);
+                let (s, e, next) = match b.get(j) {
+                    Some(&q) if q == b'"' || q == b'\'' => {
+                        let e = memchr(q, &b[j + 1..]).map_or(b.len(), |k| j + 1 + k);
+                        (j + 1, e, e + 1)
+                    }
+                    _ => {
+                        let e = word(j);
+                        (j, e, e)
+                    }
+                };
+                tag.attributes.push((attr, s, e));
+                j = next;
+            }
+            None => break,
+        }
+    }
+
+    tag
+}
+
+/// Finds the end of the declaration, like `<!DOCTYPE html>`, starting at `i`,
+/// skipping over any internal subset in brackets.
+fn declaration(b: &[u8], i: usize) -> usize {
+    let mut depth = 0;
+    for (j, &c) in b.iter().enumerate().skip(i) {
+        match c {
+            b'[' => depth += 1,
+            b']' => depth -= 1,
+            b'>' if depth <= 0 => return j + 1,
+            _ => (),
+        }
+    }
+    b.len()
+}
+
+/// Finds the end of the end tag closing the element `name`, whose content
+/// starts at `i`. Unless the element holds raw text, nested elements of the same
+/// name are skipped over.
+fn closing(text: &str, i: usize, name: &str, nested: bool, xml: bool) -> usize {
+    let b = text.as_bytes();
+    let same = |s: &str| if xml { s == name } else { s.eq_ignore_ascii_case(name) };
+    let mut depth = 1;
+    let mut j = i;
+
+    while let Some(k) = memchr(b'<', &b[j..]).map(|k| j + k) {
+        if b.get(k + 1) == Some(&b'/') {
+            let tag = tag(text, k + 2);
+            if same(tag.name) {
+                depth -= 1;
+                if depth == 0 {
+                    return tag.end;
+                }
+            }
+            j = tag.end;
+        } else if nested && b.get(k + 1).is_some_and(u8::is_ascii_alphabetic) {
+            let tag = tag(text, k + 1);
+            if same(tag.name) && !tag.closed {
+                depth += 1;
+            }
+            j = tag.end;
+        } else {
+            j = k + 1;
+        }
+    }
+
+    b.len()
+}
+
+/// Finds the character references, like `&amp;` and `&#8212;`, in a block made
+/// up of `ranges`.
+fn entities(text: &str, ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let (b, pos) = joined(text, ranges);
+    let mut holes = Vec::new();
+
+    let mut i = 0;
+    while let Some(j) = memchr(b'&', &b[i..]).map(|j| i + j) {
+        let n = b[j + 1..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphanumeric() || **c == b'#')
+            .count();
+        if n > 0 && b.get(j + 1 + n) == Some(&b';') {
+            holes.push((pos[j], pos[j + n + 1] + 1));
+            i = j + n + 2;
+        } else {
+            i = j + 1;
+        }
+    }
+
+    holes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kept(text: &str, xml: bool) -> Vec<(&str, Block)> {
+        regions(text, &Options::default(), xml)
+            .iter()
+            .map(|r| (&text[r.start..r.end], r.block))
+            .collect()
+    }
+
+    #[test]
+    fn html_text_nodes() {
+        let text = "\
+<!DOCTYPE html>
+<html><head><script>if (a < b) { very(); }</script>
+<style>p { very: 1 }</style></head>
+<body>
+<!-- very -->
+<h1 class=\"very\">The Title</h1>
+<p>Some <em>very</em> nice text, with <code>very</code> code &amp; more.
+<p>Another <img src=\"very.png\" ALT=\"A picture\"> one.</p>
+<ul><li>Item<li>Other</ul>
+<pre>very
+</pre>
+</body></html>
+";
+        assert_eq!(
+            vec![
+                ("The Title", Block::Heading),
+                ("Some", Block::Paragraph),
+                ("very", Block::Paragraph),
+                ("nice text, with", Block::Paragraph),
+                ("code ", Block::Paragraph),
+                (" more.", Block::Paragraph),
+                ("Another", Block::Paragraph),
+                ("A picture", Block::Paragraph),
+                ("one.", Block::Paragraph),
+                ("Item", Block::ListItem),
+                ("Other", Block::ListItem),
+            ],
+            kept(text, false)
+        );
+
+        let groups: Vec<_> = regions(text, &Options::default(), false)
+            .iter()
+            .map(|r| r.group)
+            .collect();
+        assert_eq!(vec![0, 1, 1, 1, 1, 1, 2, 3, 4, 5, 6], groups);
+    }
+
+    #[test]
+    fn html_docbook() {
+        let text = "\
+<?xml version=\"1.0\"?>
+<chapter><title>Setup</title>
+<para>Run <command>very</command> now.</para>
+<note><para>Be careful.</para></note>
+<programlisting><![CDATA[very]]></programlisting>
+</chapter>
+";
+        assert_eq!(
+            vec![
+                ("Setup", Block::Heading),
+                ("Run", Block::Paragraph),
+                ("now.", Block::Paragraph),
+                ("Be careful.", Block::Admonition),
+            ],
+            kept(text, true)
+        );
+    }
+
+    #[test]
+    fn html_options() {
+        let options = Options {
+            skip: vec![String::from("aside")],
+            attributes: vec![String::from("aria-label")],
+        };
+        let text = "<p title=\"Hidden\" aria-label='Label'>Text<aside>very</aside></p>";
+        let kept: Vec<_> = regions(text, &options, false)
+            .iter()
+            .map(|r| &text[r.start..r.end])
+            .collect();
+
+        assert_eq!(vec!["Label", "Text"], kept);
+    }
+}
@@ -0,0 +1,540 @@
+//! Module `latex.rs` finds the prose in LaTeX documents, skipping the preamble,
+//! comments, math, verbatim environments, command names and the arguments of
+//! commands which aren't prose, like `\ref{}` and `\cite{}`.
+
+use std::str;
+
+use markup::*;
+
+/// Commands whose argument is a heading, as in `\section{Introduction}`.
+const HEADINGS: &[&str] = &[
+    "chapter",
+    "frametitle",
+    "paragraph",
+    "part",
+    "section",
+    "subparagraph",
+    "subsection",
+    "subsubsection",
+    "title",
+];
+
+/// Commands whose arguments aren't prose.
+const SKIPPED: &[&str] = &[
+    "Cref",
+    "addbibresource",
+    "autoref",
+    "begin",
+    "bibliography",
+    "bibliographystyle",
+    "cite",
+    "citeauthor",
+    "citep",
+    "citet",
+    "cref",
+    "documentclass",
+    "end",
+    "eqref",
+    "hspace",
+    "include",
+    "includegraphics",
+    "input",
+    "label",
+    "newcommand",
+    "newenvironment",
+    "nocite",
+    "pageref",
+    "providecommand",
+    "ref",
+    "renewcommand",
+    "setcounter",
+    "setlength",
+    "texttt",
+    "url",
+    "usepackage",
+    "vspace",
+];
+
+/// Commands whose first argument isn't prose, but whose second is, as in
+/// `\href{url}{text}`.
+const TARGETS: &[&str] = &["href", "textcolor"];
+
+/// Environments whose content is math, which may continue a paragraph.
+const MATH: &[&str] = &[
+    "align",
+    "align*",
+    "alignat",
+    "alignat*",
+    "aligned",
+    "array",
+    "bmatrix",
+    "cases",
+    "displaymath",
+    "eqnarray",
+    "eqnarray*",
+    "equation",
+    "equation*",
+    "flalign",
+    "flalign*",
+    "gather",
+    "gather*",
+    "gathered",
+    "math",
+    "matrix",
+    "multline",
+    "multline*",
+    "pmatrix",
+    "split",
+];
+
+/// Environments whose content isn't prose at all.
+const VERBATIM: &[&str] = &[
+    "BVerbatim",
+    "Verbatim",
+    "comment",
+    "filecontents",
+    "lstlisting",
+    "minted",
+    "tikzpicture",
+    "verbatim",
+    "verbatim*",
+];
+
+const LISTS: &[&str] = &["description", "enumerate", "itemize"];
+
+const QUOTES: &[&str] = &["quotation", "quote", "verse"];
+
+pub fn regions(text: &str) -> Vec<Region> {
+    let mut sc = Scanner::new(text, inline_holes);
+
+    // The environment whose content we're skipping
+    let mut verbatim: Option<&str> = None;
+    // The math environment we're in, whose lines are left to `inline_holes`
+    let mut math: Option<&str> = None;
+    // Everything before `\begin{document}` is setup, except for the title
+    let mut preamble = text.contains("\\begin{document}");
+    let mut lists = 0usize;
+    let mut quotes = 0usize;
+
+    let mut offset = 0;
+    for raw in text.split_inclusive('\n') {
+        let lstart = offset;
+        offset += raw.len();
+        let line = raw.trim_end();
+        let t = line.trim_start();
+        let tstart = lstart + (line.len() - t.len());
+        let tend = lstart + line.len();
+
+        if let Some(env) = verbatim {
+            if closes(line, env) {
+                verbatim = None;
+            }
+            continue;
+        }
+        if preamble {
+            if environment(t, "\\begin") == Some("document") {
+                preamble = false;
+            } else if heading(t) {
+                sc.push(Block::Heading, tstart, tend);
+                sc.flush();
+            }
+            continue;
+        }
+        if environment(t, "\\end") == Some("document") {
+            break;
+        }
+
+        if let Some(env) = math {
+            if closes(line, env) {
+                math = None;
+            }
+            sc.push(block(lists, quotes), tstart, tend);
+            continue;
+        }
+
+        if t.is_empty() {
+            sc.flush();
+            continue;
+        }
+        if t.starts_with('%') {
+            // A comment doesn't end a paragraph
+            continue;
+        }
+
+        if let Some(env) = environment(t, "\\begin") {
+            if MATH.contains(&env) {
+                if !closes(line, env) {
+                    math = Some(env);
+                }
+                sc.push(block(lists, quotes), tstart, tend);
+                continue;
+            }
+
+            sc.flush();
+            if VERBATIM.contains(&env) {
+                if !closes(line, env) {
+                    verbatim = Some(env);
+                }
+                continue;
+            }
+            if LISTS.contains(&env) {
+                lists += 1;
+            } else if QUOTES.contains(&env) {
+                quotes += 1;
+            }
+        } else if let Some(env) = environment(t, "\\end") {
+            if !MATH.contains(&env) {
+                sc.flush();
+                if LISTS.contains(&env) {
+                    lists = lists.saturating_sub(1);
+                } else if QUOTES.contains(&env) {
+                    quotes = quotes.saturating_sub(1);
+                }
+            }
+        } else if heading(t) {
+            sc.flush();
+            sc.push(Block::Heading, tstart, tend);
+            sc.flush();
+            continue;
+        } else if command(t) == Some("item") {
+            sc.flush();
+            sc.push(Block::ListItem, tstart, tend);
+            continue;
+        }
+
+        sc.push(block(lists, quotes), tstart, tend);
+    }
+
+    sc.flush();
+    sc.res
+}
+
+/// Returns the block of a paragraph inside of `lists` lists and `quotes`
+/// quotes.
+fn block(lists: usize, quotes: usize) -> Block {
+    if quotes > 0 {
+        Block::BlockQuote
+    } else if lists > 0 {
+        Block::ListItem
+    } else {
+        Block::Paragraph
+    }
+}
+
+/// Returns the name of the command at the start of `t`, if there is one.
+fn command(t: &str) -> Option<&str> {
+    let name = t.strip_prefix('\\')?;
+    let w = name.bytes().take_while(u8::is_ascii_alphabetic).count();
+    if w == 0 {
+        None
+    } else {
+        Some(&name[..w])
+    }
+}
+
+/// Returns the environment that `t` begins or ends, if it starts with the
+/// `\begin` or `\end` given as `cmd`.
+fn environment<'t>(t: &'t str, cmd: &str) -> Option<&'t str> {
+    let rest = t.strip_prefix(cmd)?.trim_start().strip_prefix('{')?;
+    rest.find('}').map(|i| &rest[..i])
+}
+
+/// Whether `line` ends the environment `env`.
+fn closes(line: &str, env: &str) -> bool {
+    line.contains(&format!("\\end{{{}}}", env))
+}
+
+/// Whether `t` starts with a heading command, like `\section*{Title}`.
+fn heading(t: &str) -> bool {
+    match command(t) {
+        Some(name) if HEADINGS.contains(&name) => {
+            t[name.len() + 1..].starts_with(['*', '[', '{'])
+        }
+        _ => false,
+    }
+}
+
+/// Returns the position after the group opened by the `open` at `i` in `b`,
+/// if it's closed.
+fn group(b: &[u8], i: usize, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    let mut j = i;
+    while j < b.len() {
+        match b[j] {
+            b'\\' => j += 1,
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(j + 1);
+                }
+            }
+            _ => {}
+        }
+        j += 1;
+    }
+    None
+}
+
+/// Returns the position where the math opened before `from` is closed by
+/// `delim`, skipping escaped dollar signs.
+fn math_end(b: &[u8], from: usize, delim: &[u8]) -> Option<usize> {
+    let mut from = from;
+    while let Some(j) = find(b, from, delim) {
+        if b[j - 1] != b'\\' {
+            return Some(j);
+        }
+        from = j + 1;
+    }
+    None
+}
+
+/// Finds the inline markup in a block made up of `ranges`, returning the
+/// ranges of bytes which aren't prose: comments, math, command names, braces,
+/// optional arguments and the arguments of commands which aren't prose.
+fn inline_holes(text: &str, ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let (b, pos) = joined(text, ranges);
+    let n = b.len();
+
+    let mut holes: Vec<(usize, usize)> = Vec::new();
+    let hole = |i: usize, j: usize, holes: &mut Vec<(usize, usize)>| {
+        if i < j {
+            holes.push((pos[i], pos[j - 1] + 1));
+        }
+    };
+
+    let mut i = 0;
+    while i < n {
+        match b[i] {
+            b'%' => {
+                let j = b[i..].iter().position(|&c| c == b'\n').map_or(n, |j| i + j);
+                hole(i, j, &mut holes);
+                i = j;
+            }
+            b'$' => {
+                let r = if b[i..].starts_with(b"$$") { 2 } else { 1 };
+                let j = math_end(&b, i + r, &b[i..i + r]).map_or(n, |j| j + r);
+                hole(i, j, &mut holes);
+                i = j;
+            }
+            b'{' | b'}' | b'&' => {
+                hole(i, i + 1, &mut holes);
+                i += 1;
+            }
+            b'\\' if i + 1 < n && b[i + 1].is_ascii_alphabetic() => {
+                let w = b[i + 1..].iter().take_while(|c| c.is_ascii_alphabetic()).count();
+                let name = str::from_utf8(&b[i + 1..i + 1 + w]).unwrap_or("");
+                let mut e = i + 1 + w;
+
+                if name == "verb" || name == "lstinline" {
+                    // Inline code, closed by the character that opens it
+                    e += (b.get(e) == Some(&b'*')) as usize;
+                    let close = match b.get(e) {
+                        Some(&b'{') => group(&b, e, b'{', b'}'),
+                        Some(&d) => {
+                            let w = width(d).min(n - e);
+                            find(&b, e + w, &b[e..e + w]).map(|j| j + w)
+                        }
+                        None => None,
+                    };
+                    let j = close.unwrap_or(n);
+                    hole(i, j, &mut holes);
+                    i = j;
+                    continue;
+                }
+
+                if name == "begin" {
+                    let env = b.get(e..).and_then(|rest| {
+                        let rest = str::from_utf8(rest).ok()?;
+                        environment(rest, "")
+                    });
+                    if let Some(env) = env.filter(|env| MATH.contains(env)) {
+                        let end = format!("\\end{{{}}}", env);
+                        let j = find(&b, e, end.as_bytes()).map_or(n, |j| j + end.len());
+                        hole(i, j, &mut holes);
+                        i = j;
+                        continue;
+                    }
+                }
+
+                e += (b.get(e) == Some(&b'*')) as usize;
+                let args = if SKIPPED.contains(&name) {
+                    usize::MAX
+                } else if TARGETS.contains(&name) {
+                    1
+                } else {
+                    0
+                };
+
+                // Optional arguments are never prose, and neither are the
+                // arguments of commands like `\ref`
+                let mut skipped = 0;
+                loop {
+                    let close = match b.get(e) {
+                        Some(&b'[') => group(&b, e, b'[', b']'),
+                        Some(&b'{') if skipped < args => {
+                            skipped += 1;
+                            group(&b, e, b'{', b'}')
+                        }
+                        _ => None,
+                    };
+                    match close {
+                        Some(j) => e = j,
+                        None => break,
+                    }
+                }
+                hole(i, e, &mut holes);
+                i = e;
+            }
+            b'\\' if i + 1 < n => {
+                let close: &[u8] = match b[i + 1] {
+                    b'(' => b"\\)",
+                    b'[' => b"\\]",
+                    _ => b"",
+                };
+                let j = if close.is_empty() {
+                    // An escaped character, or a symbol like `\\`
+                    (i + 1 + width(b[i + 1])).min(n)
+                } else {
+                    find(&b, i + 2, close).map_or(n, |j| j + 2)
+                };
+                hole(i, j, &mut holes);
+                i = j;
+            }
+            _ => i += 1,
+        }
+    }
+
+    holes.sort();
+    holes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prose(text: &str) -> String {
+        mask(text, &regions(text))
+    }
+
+    fn kept(text: &str) -> Vec<(&str, Block)> {
+        regions(text)
+            .iter()
+            .map(|r| (&text[r.start..r.end], r.block))
+            .collect()
+    }
+
+    #[test]
+    fn latex_skipped() {
+        let text = "\
+\\documentclass{article}
+\\usepackage{very}
+\\title{A Paper}
+\\begin{document}
+% a very comment
+\\begin{verbatim}
+very
+
+verbatim
+\\end{verbatim}
+\\begin{equation}
+  very = 1
+\\end{equation}
+Done.
+\\end{document}
+very
+";
+        assert_eq!(
+            vec![("A Paper", Block::Heading), ("Done.", Block::Paragraph)],
+            kept(text)
+        );
+    }
+
+    #[test]
+    fn latex_blocks() {
+        let text = "\
+\\section*{Intro}\\label{sec:intro}
+Some text
+here.
+
+\\begin{itemize}
+  \\item one
+  continued
+  \\item[b)] two
+\\end{itemize}
+After.
+
+\\begin{quote}
+quoted
+\\end{quote}
+";
+        assert_eq!(
+            vec![
+                ("Intro", Block::Heading),
+                ("Some text", Block::Paragraph),
+                ("here.", Block::Paragraph),
+                (" one", Block::ListItem),
+                ("continued", Block::ListItem),
+                (" two", Block::ListItem),
+                ("After.", Block::Paragraph),
+                ("quoted", Block::BlockQuote),
+            ],
+            kept(text)
+        );
+    }
+
+    #[test]
+    fn latex_inline() {
+        let text = "See \\emph{very} \\ref{very}, $very$ \\(x\\) \\verb|very| and \
+                    \\href{very.html}{a link}~\\cite[p.~2]{very}. % very\n";
+        assert_eq!(
+            "See       very            ,                          and \
+             \x20                a link ~                 .       \n",
+            prose(text)
+        );
+    }
+
+    #[test]
+    fn latex_math_paragraph() {
+        let text = "\
+We have
+\\begin{align}
+  a &= b \\\\
+  \\begin{cases} very \\end{cases}
+\\end{align}
+where $a$ is very.
+";
+        assert_eq!(
+            vec![
+                ("We have", Block::Paragraph),
+                ("where ", Block::Paragraph),
+                (" is very.", Block::Paragraph),
+            ],
+            kept(text)
+        );
+
+        let groups: Vec<_> = regions(text).iter().map(|r| r.group).collect();
+        assert_eq!(vec![0, 0, 0], groups);
+    }
+
+    #[test]
+    fn latex_offsets() {
+        let text = "\\section{A}\n\n$x$ very\n";
+        let masked = prose(text);
+        assert_eq!(text.len(), masked.len());
+        assert_eq!(text.find("very"), masked.find("very"));
+    }
+
+    #[test]
+    fn latex_non_ascii() {
+        let text = "x \\é very \\verb§a§ very \\verb§b\n";
+        assert_eq!(
+            vec![
+                ("x ", Block::Paragraph),
+                (" very ", Block::Paragraph),
+                (" very ", Block::Paragraph),
+            ],
+            kept(text)
+        );
+    }
+}
@@ -0,0 +1,504 @@
+//! Module `markdown.rs` finds the prose in Markdown documents, skipping front
+//! matter, code, html, link targets and urls.
+
+use memchr::memchr;
+use std::cmp;
+
+use markup::*;
+
+pub fn regions(text: &str) -> Vec<Region> {
+    let bytes = text.as_bytes();
+    let mut sc = Scanner::new(text, inline_holes);
+
+    // The fence character and length of the code block we're in
+    let mut fence: Option<(u8, usize)> = None;
+    let mut comment = false;
+    let mut html = false;
+    let mut prev_blank = true;
+    // The column that the content of the current list item starts at
+    let mut list_indent: Option<usize> = None;
+
+    let mut start = front_matter(text);
+    while start < text.len() {
+        let next = memchr(b'\n', &bytes[start..]).map_or(text.len(), |i| start + i + 1);
+        let mut end = next;
+        while end > start && (bytes[end - 1] == b'\n' || bytes[end - 1] == b'\r') {
+            end -= 1;
+        }
+        let lstart = start;
+        let line = &text[start..end];
+        start = next;
+
+        if let Some((c, n)) = fence {
+            let t = line.trim_start();
+            if run(t.as_bytes(), c) >= n && t.bytes().all(|b| b == c || b == b' ') {
+                fence = None;
+            }
+            continue;
+        }
+
+        if comment {
+            if line.contains("-->") {
+                comment = false;
+            }
+            continue;
+        }
+
+        if html {
+            if line.trim().is_empty() {
+                html = false;
+                prev_blank = true;
+            }
+            continue;
+        }
+
+        let (quoted, cstart) = strip_quotes(line);
+        let content = &line[cstart..];
+
+        if content.trim().is_empty() {
+            sc.flush();
+            prev_blank = true;
+            continue;
+        }
+
+        let indent = indentation(content);
+        let t = content.trim_start();
+        let tstart = lstart + cstart + (content.len() - t.len());
+        let tend = lstart + line.trim_end().len();
+        let tb = t.as_bytes();
+        let paragraph = sc.pending.is_some() && !prev_blank;
+        prev_blank = false;
+
+        if let Some(li) = list_indent {
+            if indent < li && sc.pending.is_none() && list_marker(t).is_none() {
+                list_indent = None;
+            }
+        }
+
+        if indent >= list_indent.unwrap_or(0) + 4 && !paragraph {
+            // Indented code block
+            sc.flush();
+            continue;
+        }
+
+        let n = cmp::max(run(tb, b'`'), run(tb, b'~'));
+        if n >= 3 && !(tb[0] == b'`' && t[n..].contains('`')) {
+            sc.flush();
+            fence = Some((tb[0], n));
+            continue;
+        }
+
+        if let Some(rest) = t.strip_prefix("<!--") {
+            sc.flush();
+            comment = !rest.contains("-->");
+            continue;
+        }
+
+        if !paragraph && html_tag(tb) {
+            sc.flush();
+            html = true;
+            continue;
+        }
+
+        if paragraph && underline(t) {
+            // Setext heading
+            if let Some(ref mut p) = sc.pending {
+                p.block = Block::Heading;
+            }
+            sc.flush();
+            continue;
+        }
+
+        if thematic_break(t) {
+            sc.flush();
+            continue;
+        }
+
+        let hashes = run(tb, b'#');
+        let atx = tb.len() == hashes || tb[hashes] == b' ' || tb[hashes] == b'\t';
+        if (1..=6).contains(&hashes) && atx {
+            sc.flush();
+            let h = t[hashes..].trim();
+            // Strip the optional closing sequence of #s
+            let stripped = h.trim_end_matches('#');
+            let h = if stripped.is_empty() || stripped.ends_with([' ', '\t']) {
+                stripped.trim_end()
+            } else {
+                h
+            };
+            if !h.is_empty() {
+                let after = &t[hashes..];
+                let hs = tstart + hashes + (after.len() - after.trim_start().len());
+                sc.push(Block::Heading, hs, hs + h.len());
+                sc.flush();
+            }
+            continue;
+        }
+
+        if !paragraph && link_definition(t) {
+            sc.flush();
+            continue;
+        }
+
+        if t.contains('|') && table_delimiter(t) {
+            continue;
+        }
+
+        if let Some(m) = list_marker(t) {
+            sc.flush();
+            let rest = &t[m..];
+            let item = rest.trim_start();
+            list_indent = Some(indent + m + rest.len() - item.len());
+            if !item.is_empty() {
+                let s = tstart + m + (rest.len() - item.len());
+                sc.push(Block::ListItem, s, tend);
+            }
+            continue;
+        }
+
+        let block = if list_indent.is_some() {
+            Block::ListItem
+        } else if quoted {
+            Block::BlockQuote
+        } else {
+            Block::Paragraph
+        };
+        sc.push(block, tstart, tend);
+    }
+
+    sc.flush();
+    sc.res
+}
+
+/// Returns the length of YAML (`---`) or TOML (`+++`) front matter at the start
+/// of `text`, including its closing line.
+fn front_matter(text: &str) -> usize {
+    let delim = if text.starts_with("---") {
+        "---"
+    } else if text.starts_with("+++") {
+        "+++"
+    } else {
+        return 0;
+    };
+
+    let mut lines = text.split('\n');
+    if lines.next().map(str::trim_end) != Some(delim) {
+        return 0;
+    }
+
+    let mut len = delim.len() + 1;
+    for line in lines {
+        len += line.len() + 1;
+        let l = line.trim_end();
+        if l == delim || (delim == "---" && l == "...") {
+            return len.min(text.len());
+        }
+    }
+
+    0
+}
+
+/// Strips any block quote markers from the start of `line`, returning whether
+/// there were any and where the content starts.
+fn strip_quotes(line: &str) -> (bool, usize) {
+    let b = line.as_bytes();
+    let mut i = 0;
+    let mut quoted = false;
+
+    loop {
+        let mut j = i;
+        while j < b.len() && j - i < 3 && b[j] == b' ' {
+            j += 1;
+        }
+        if j < b.len() && b[j] == b'>' {
+            quoted = true;
+            i = j + 1;
+            if i < b.len() && b[i] == b' ' {
+                i += 1;
+            }
+        } else {
+            return (quoted, i);
+        }
+    }
+}
+
+/// Returns the length of the list marker at the start of `t`, if there is one.
+fn list_marker(t: &str) -> Option<usize> {
+    let b = t.as_bytes();
+    let m = match b.first() {
+        Some(&b'-') | Some(&b'*') | Some(&b'+') => 1,
+        Some(c) if c.is_ascii_digit() => {
+            let n = b.iter().take_while(|c| c.is_ascii_digit()).count();
+            if n > 9 || n >= b.len() || (b[n] != b'.' && b[n] != b')') {
+                return None;
+            }
+            n + 1
+        }
+        _ => return None,
+    };
+
+    if m == b.len() || b[m] == b' ' || b[m] == b'\t' {
+        Some(m)
+    } else {
+        None
+    }
+}
+
+fn thematic_break(t: &str) -> bool {
+    let t = t.trim_end();
+    match t.bytes().next() {
+        Some(c) if c == b'*' || c == b'-' || c == b'_' => {
+            t.bytes().all(|b| b == c || b == b' ' || b == b'\t')
+                && t.bytes().filter(|&b| b == c).count() >= 3
+        }
+        _ => false,
+    }
+}
+
+fn underline(t: &str) -> bool {
+    let t = t.trim_end();
+    match t.bytes().next() {
+        Some(c) if c == b'=' || c == b'-' => t.bytes().all(|b| b == c),
+        _ => false,
+    }
+}
+
+/// Whether `t` starts with an html tag, like `<div>` or `</p>`.
+fn html_tag(t: &[u8]) -> bool {
+    let mut i = 1;
+    if t.get(i) == Some(&b'/') {
+        i += 1;
+    }
+    if !t.get(i).is_some_and(|c| c.is_ascii_alphabetic()) {
+        return false;
+    }
+    while t.get(i).is_some_and(|&c| c.is_ascii_alphanumeric() || c == b'-') {
+        i += 1;
+    }
+    t[0] == b'<' && t.get(i).is_none_or(|&c| c == b'>' || c == b'/' || c == b' ' || c == b'\t')
+}
+
+/// Whether `t` is a link reference definition, like `[label]: url`.
+fn link_definition(t: &str) -> bool {
+    if !t.starts_with('[') {
+        return false;
+    }
+    match t.find("]:") {
+        Some(i) => i > 1 && !t[1..i].contains(']'),
+        None => false,
+    }
+}
+
+/// Whether `t` is the delimiter row of a table, like `| --- | :-: |`.
+fn table_delimiter(t: &str) -> bool {
+    let t = t.trim().trim_start_matches('|').trim_end_matches('|');
+    !t.is_empty()
+        && t.split('|').all(|cell| {
+            let c = cell.trim().trim_start_matches(':').trim_end_matches(':');
+            !c.is_empty() && c.bytes().all(|b| b == b'-')
+        })
+}
+
+/// Finds the inline markup in a block made up of `ranges`, returning the
+/// ranges of bytes which aren't prose: code spans, html, urls, link targets and
+/// the brackets around link text.
+fn inline_holes(text: &str, ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let (b, pos) = joined(text, ranges);
+    let n = b.len();
+
+    let mut holes: Vec<(usize, usize)> = Vec::new();
+    let hole = |i: usize, j: usize, holes: &mut Vec<(usize, usize)>| {
+        if i < j {
+            holes.push((pos[i], pos[j - 1] + 1));
+        }
+    };
+    // Positions of closing link brackets, and where their targets end
+    let mut jumps: Vec<(usize, usize)> = Vec::new();
+
+    let mut i = 0;
+    while i < n {
+        if let Some(k) = jumps.iter().position(|&(at, _)| at == i) {
+            let (_, to) = jumps.remove(k);
+            i = to;
+            continue;
+        }
+
+        match b[i] {
+            b'\\' if i + 1 < n && b[i + 1].is_ascii_punctuation() => {
+                hole(i, i + 1, &mut holes);
+                i += 2;
+            }
+            b'`' => {
+                let r = run(&b[i..], b'`');
+                let mut j = i + r;
+                let mut close = None;
+                while j < n {
+                    if b[j] == b'`' {
+                        let rr = run(&b[j..], b'`');
+                        if rr == r {
+                            close = Some(j + rr);
+                            break;
+                        }
+                        j += rr;
+                    } else {
+                        j += 1;
+                    }
+                }
+                match close {
+                    Some(c) => {
+                        hole(i, c, &mut holes);
+                        i = c;
+                    }
+                    None => i += r,
+                }
+            }
+            b'<' => {
+                let end = if b[i..].starts_with(b"<!--") {
+                    find(&b, i + 4, b"-->").map(|j| j + 3)
+                } else if i + 1 < n
+                    && (b[i + 1].is_ascii_alphabetic() || b[i + 1] == b'/')
+                {
+                    b[i..]
+                        .iter()
+                        .position(|&c| c == b'>')
+                        .map(|j| i + j + 1)
+                } else {
+                    None
+                };
+                match end {
+                    Some(e) => {
+                        hole(i, e, &mut holes);
+                        i = e;
+                    }
+                    None => i += 1,
+                }
+            }
+            b'!' if i + 1 < n && b[i + 1] == b'[' => {
+                hole(i, i + 1, &mut holes);
+                i += 1;
+            }
+            b'[' => {
+                if let Some(close) = matching(&b, i, b'[', b']') {
+                    let target = match b.get(close + 1) {
+                        Some(&b'(') => matching(&b, close + 1, b'(', b')').map(|e| e + 1),
+                        Some(&b'[') => matching(&b, close + 1, b'[', b']').map(|e| e + 1),
+                        _ => None,
+                    };
+                    let to = target.unwrap_or(close + 1);
+                    hole(i, i + 1, &mut holes);
+                    hole(close, to, &mut holes);
+                    jumps.push((close, to));
+                }
+                i += 1;
+            }
+            b'h' | b'w' if i == 0 || !b[i - 1].is_ascii_alphanumeric() => {
+                match url(&b, i) {
+                    Some(e) => {
+                        hole(i, e, &mut holes);
+                        i = e;
+                    }
+                    None => i += 1,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    holes.sort();
+    holes
+}
+
+/// Finds the bracket closing the one at `open`, skipping over nested pairs.
+fn matching(b: &[u8], open: usize, l: u8, r: u8) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i < b.len() {
+        match b[i] {
+            b'\\' => i += 1,
+            c if c == l => depth += 1,
+            c if c == r => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prose(text: &str) -> String {
+        mask(text, &regions(text))
+    }
+
+    fn kept(text: &str) -> Vec<&str> {
+        regions(text)
+            .iter()
+            .map(|r| &text[r.start..r.end])
+            .collect()
+    }
+
+    #[test]
+    fn markdown_front_matter() {
+        let text = "---\ntitle: very\n---\nSome text.\n";
+        assert_eq!(vec!["Some text."], kept(text));
+    }
+
+    #[test]
+    fn markdown_code_blocks() {
+        let text = "Text.\n\n```rust\nlet very = 1;\n```\n\n    indented code\n\nMore.\n";
+        assert_eq!(vec!["Text.", "More."], kept(text));
+    }
+
+    #[test]
+    fn markdown_inline() {
+        let text = "Use `very` and [the docs](http://very.com) or <b>x</b>.\n";
+        assert_eq!(
+            "Use        and  the docs                   or    x    .\n",
+            prose(text)
+        );
+    }
+
+    #[test]
+    fn markdown_comments_and_urls() {
+        let text = "<!-- a\nvery\ncomment -->\nSee https://very.example.com/x.\n";
+        assert_eq!(vec!["See ", "."], kept(text));
+    }
+
+    #[test]
+    fn markdown_blocks() {
+        let text = "# Title #\n\nSetext\n======\n\n- item one\n  continued\n\n> quoted\n";
+        let blocks: Vec<_> = regions(text)
+            .iter()
+            .map(|r| (&text[r.start..r.end], r.block))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("Title", Block::Heading),
+                ("Setext", Block::Heading),
+                ("item one", Block::ListItem),
+                ("continued", Block::ListItem),
+                ("quoted", Block::BlockQuote),
+            ],
+            blocks
+        );
+
+        let groups: Vec<_> = regions(text).iter().map(|r| r.group).collect();
+        assert_eq!(vec![0, 1, 2, 2, 3], groups);
+    }
+
+    #[test]
+    fn markdown_offsets() {
+        let text = "# A\n\n`x` very\n";
+        let masked = prose(text);
+        assert_eq!(text.len(), masked.len());
+        assert_eq!(text.find("very"), masked.find("very"));
+    }
+}
@@ -0,0 +1,434 @@
+//! Module `markup` finds the regions of a document which hold prose, so that
+//! lints aren't run over code, urls and other markup.
+//!
+//! Every front-end produces byte ranges into the original text, and `mask`
+//! blanks out everything outside of them without moving any bytes. Lints can
+//! then be run over the masked text directly, and the positions they report
+//! still point into the original file.
+
+pub mod asciidoc;
+pub mod code;
+pub mod html;
+pub mod latex;
+pub mod markdown;
+pub mod rst;
+
+use failure::Error;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The markup language a document is written in.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Text,
+    Markdown,
+    Rst,
+    AsciiDoc,
+    Html,
+    Xml,
+    Latex,
+    /// The comments and docstrings of source code.
+    Code(code::Language),
+}
+
+impl Format {
+    /// Guesses the format of a file from its extension, falling back to plain
+    /// text.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Format {
+        let ext = path.as_ref()
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(|e| e.to_lowercase());
+
+        match ext.as_ref().map(|e| &e[..]) {
+            Some("md") | Some("markdown") | Some("mdown") | Some("mkd") | Some("mkdn") => {
+                Format::Markdown
+            }
+            Some("rst") | Some("rest") => Format::Rst,
+            Some("adoc") | Some("asciidoc") => Format::AsciiDoc,
+            Some("html") | Some("htm") | Some("xhtml") => Format::Html,
+            Some("xml") | Some("dbk") | Some("docbook") => Format::Xml,
+            Some("tex") | Some("latex") | Some("ltx") => Format::Latex,
+            Some(ext) => code::Language::from_extension(ext).map_or(Format::Text, Format::Code),
+            None => Format::Text,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "text" => Ok(Format::Text),
+            "markdown" => Ok(Format::Markdown),
+            "rst" => Ok(Format::Rst),
+            "asciidoc" => Ok(Format::AsciiDoc),
+            "html" => Ok(Format::Html),
+            "xml" => Ok(Format::Xml),
+            "latex" => Ok(Format::Latex),
+            other => other
+                .parse()
+                .map(Format::Code)
+                .map_err(|_| format_err!("unknown format `{}`", s)),
+        }
+    }
+}
+
+/// The kind of block a region of prose belongs to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Block {
+    Heading,
+    Paragraph,
+    ListItem,
+    BlockQuote,
+    /// The content of a note, warning or other admonition.
+    Admonition,
+}
+
+/// A range of bytes in a document which holds prose.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Region {
+    pub start: usize,
+    pub end: usize,
+    pub block: Block,
+    /// Regions of the same heading, paragraph, list item, quote or admonition
+    /// share a group, numbered in document order.
+    pub group: usize,
+    /// Whether inline markup, like a code span, was cut out between this
+    /// region and the one before it. Phrases can't run across such a cut.
+    pub after_hole: bool,
+}
+
+/// Whether the range from `start` to `end` runs across inline markup, like a
+/// code span, which was cut out between two of the sorted `regions`.
+pub fn crosses_hole(regions: Option<&[Region]>, start: usize, end: usize) -> bool {
+    regions.is_some_and(|rs| {
+        let i = rs.partition_point(|r| r.start <= start);
+        rs[i..].iter().take_while(|r| r.start < end).any(|r| r.after_hole)
+    })
+}
+
+/// The settings of the front-ends which can be configured.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Options {
+    /// Which parts of HTML and XML documents are prose.
+    pub html: html::Options,
+}
+
+/// Finds the regions of prose in `text`, in order. Returns `None` if the whole
+/// of `text` is prose.
+pub fn regions(format: Format, text: &str, options: &Options) -> Option<Vec<Region>> {
+    match format {
+        Format::Text => None,
+        Format::Markdown => Some(markdown::regions(text)),
+        Format::Rst => Some(rst::regions(text)),
+        Format::AsciiDoc => Some(asciidoc::regions(text)),
+        Format::Html => Some(html::regions(text, &options.html, false)),
+        Format::Xml => Some(html::regions(text, &options.html, true)),
+        Format::Latex => Some(latex::regions(text)),
+        Format::Code(language) => Some(code::regions(text, language)),
+    }
+}
+
+/// Finds the byte ranges of the paragraphs of `text`. With `regions`, each
+/// group of regions makes up one paragraph; otherwise paragraphs are separated
+/// by blank lines.
+pub fn paragraphs(text: &str, regions: Option<&[Region]>) -> Vec<(usize, usize)> {
+    let mut res: Vec<(usize, usize)> = Vec::new();
+
+    match regions {
+        Some(regions) => {
+            let mut last = None;
+            for r in regions {
+                if last == Some(r.group) {
+                    res.last_mut().unwrap().1 = r.end;
+                } else {
+                    res.push((r.start, r.end));
+                }
+                last = Some(r.group);
+            }
+        }
+        None => {
+            let mut start = None;
+            let mut offset = 0;
+            for line in text.split_inclusive('\n') {
+                if line.trim().is_empty() {
+                    if let Some(s) = start.take() {
+                        res.push((s, offset));
+                    }
+                } else if start.is_none() {
+                    start = Some(offset);
+                }
+                offset += line.len();
+            }
+            if let Some(s) = start {
+                res.push((s, text.len()));
+            }
+        }
+    }
+
+    res
+}
+
+/// Replaces every character of `text` which lies outside of `regions` with
+/// spaces, keeping line breaks and the byte offset of every character intact.
+/// `regions` must be sorted and non-overlapping.
+pub fn mask(text: &str, regions: &[Region]) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut rs = regions.iter().peekable();
+
+    for (i, c) in text.char_indices() {
+        while rs.peek().is_some_and(|r| r.end <= i) {
+            rs.next();
+        }
+
+        let inside = rs.peek().is_some_and(|r| r.start <= i);
+        if inside || c == '\n' {
+            res.push(c);
+        } else {
+            for _ in 0..c.len_utf8() {
+                res.push(' ');
+            }
+        }
+    }
+
+    res
+}
+
+/// A block of prose which is still being read, as the content ranges of each of
+/// its lines.
+pub(crate) struct Pending {
+    pub block: Block,
+    pub ranges: Vec<(usize, usize)>,
+}
+
+/// Finds the byte ranges of the inline markup in a block made up of the given
+/// ranges of a text.
+pub(crate) type Holes = fn(&str, &[(usize, usize)]) -> Vec<(usize, usize)>;
+
+/// Collects the blocks of prose that a front-end finds, carving the inline
+/// markup found by `holes` out of each of them.
+pub(crate) struct Scanner<'a> {
+    pub text: &'a str,
+    pub res: Vec<Region>,
+    pub pending: Option<Pending>,
+    holes: Holes,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(text: &'a str, holes: Holes) -> Self {
+        Scanner {
+            text,
+            res: Vec::new(),
+            pending: None,
+            holes,
+        }
+    }
+
+    /// Adds a line of prose to the block being read, starting a new block if
+    /// there isn't one.
+    pub fn push(&mut self, block: Block, start: usize, end: usize) {
+        match self.pending {
+            Some(ref mut p) => p.ranges.push((start, end)),
+            None => {
+                self.pending = Some(Pending {
+                    block,
+                    ranges: vec![(start, end)],
+                })
+            }
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(p) = self.pending.take() {
+            let holes = (self.holes)(self.text, &p.ranges);
+            carve(&p.ranges, &holes, p.block, &mut self.res);
+        }
+    }
+}
+
+/// Returns the width of the whitespace at the start of `s`, with tabs expanded
+/// to the next multiple of four columns.
+pub(crate) fn indentation(s: &str) -> usize {
+    let mut n = 0;
+    for b in s.bytes() {
+        match b {
+            b' ' => n += 1,
+            b'\t' => n += 4 - n % 4,
+            _ => break,
+        }
+    }
+    n
+}
+
+/// The length of the UTF-8 character starting with the byte `c`.
+pub(crate) fn width(c: u8) -> usize {
+    match c {
+        0xf0..=0xff => 4,
+        0xe0..=0xef => 3,
+        0xc0..=0xdf => 2,
+        _ => 1,
+    }
+}
+
+pub(crate) fn run(s: &[u8], c: u8) -> usize {
+    s.iter().take_while(|&&b| b == c).count()
+}
+
+pub(crate) fn find(b: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    b[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|j| from + j)
+}
+
+/// Returns the end of the url starting at `i` in `b`, if there is one. Any
+/// punctuation trailing the url is left out of it.
+pub(crate) fn url(b: &[u8], i: usize) -> Option<usize> {
+    let starts = b[i..].starts_with(b"http://") || b[i..].starts_with(b"https://")
+        || b[i..].starts_with(b"www.");
+    if !starts {
+        return None;
+    }
+
+    let mut e = i;
+    while e < b.len() && !b[e].is_ascii_whitespace() && b[e] != b'<' {
+        e += 1;
+    }
+    while e > i && b".,:;!?)'\"".contains(&b[e - 1]) {
+        e -= 1;
+    }
+    Some(e)
+}
+
+/// Joins the lines of a block made up of `ranges` of `text` with newlines,
+/// returning its bytes along with the position of each of them in `text`.
+pub(crate) fn joined(text: &str, ranges: &[(usize, usize)]) -> (Vec<u8>, Vec<usize>) {
+    let mut b = Vec::new();
+    let mut pos = Vec::new();
+    for (k, &(s, e)) in ranges.iter().enumerate() {
+        if k > 0 {
+            b.push(b'\n');
+            pos.push(ranges[k - 1].1);
+        }
+        b.extend_from_slice(&text.as_bytes()[s..e]);
+        pos.extend(s..e);
+    }
+
+    (b, pos)
+}
+
+/// Subtracts the sorted ranges in `holes` from `ranges`, producing regions of
+/// the given block.
+pub(crate) fn carve(
+    ranges: &[(usize, usize)],
+    holes: &[(usize, usize)],
+    block: Block,
+    res: &mut Vec<Region>,
+) {
+    let mut hs = holes.iter().peekable();
+    let group = res.last().map_or(0, |r| r.group + 1);
+    // Whether a hole was cut out since the last region
+    let mut cut = false;
+
+    for &(start, end) in ranges {
+        let mut cur = start;
+        while let Some(&&(hs_, he)) = hs.peek() {
+            if hs_ >= end {
+                break;
+            }
+            if he > cur {
+                if hs_ > cur {
+                    res.push(Region {
+                        start: cur,
+                        end: hs_,
+                        block,
+                        group,
+                        after_hole: cut,
+                    });
+                }
+                cur = he;
+                cut = true;
+            }
+            if he <= end {
+                hs.next();
+            } else {
+                break;
+            }
+        }
+        if cur < end {
+            res.push(Region {
+                start: cur,
+                end,
+                block,
+                group,
+                after_hole: cut,
+            });
+            cut = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(Format::Markdown, Format::from_path("docs/intro.md"));
+        assert_eq!(Format::Markdown, Format::from_path("README.MARKDOWN"));
+        assert_eq!(Format::Rst, Format::from_path("docs/index.rst"));
+        assert_eq!(Format::AsciiDoc, Format::from_path("manual.adoc"));
+        assert_eq!(Format::Html, Format::from_path("help/index.HTM"));
+        assert_eq!(Format::Xml, Format::from_path("book.xml"));
+        assert_eq!(Format::Latex, Format::from_path("paper/main.tex"));
+        assert_eq!(Format::Code(code::Language::Rust), Format::from_path("src/lib.rs"));
+        assert_eq!(Format::Code(code::Language::C), Format::from_path("include/a.hpp"));
+        assert_eq!(Format::Text, Format::from_path("notes.txt"));
+        assert_eq!(Format::Text, Format::from_path("<stdin>"));
+    }
+
+    #[test]
+    fn mask_keeps_offsets() {
+        let text = "ab `é` cd\nef";
+        let regions = [
+            Region {
+                start: 0,
+                end: 3,
+                block: Block::Paragraph,
+                group: 0,
+                after_hole: false,
+            },
+            Region {
+                start: 8,
+                end: 13,
+                block: Block::Paragraph,
+                group: 0,
+                after_hole: false,
+            },
+        ];
+        let masked = mask(text, &regions);
+
+        assert_eq!(text.len(), masked.len());
+        assert_eq!("ab      cd\nef", masked);
+    }
+
+    #[test]
+    fn carve_holes() {
+        let mut res = Vec::new();
+        carve(&[(0, 10), (12, 20)], &[(2, 4), (8, 14)], Block::Paragraph, &mut res);
+
+        let spans: Vec<_> = res.iter().map(|r| (r.start, r.end, r.after_hole)).collect();
+        assert_eq!(vec![(0, 2, false), (4, 8, true), (14, 20, true)], spans);
+    }
+
+    #[test]
+    fn paragraphs_text() {
+        let text = "a b\nc\n\n  \nd\n";
+        assert_eq!(vec![(0, 6), (10, 12)], paragraphs(text, None));
+    }
+}
@@ -0,0 +1,691 @@
+//! Module `rst.rs` finds the prose in reStructuredText documents, including
+//! Sphinx sources, skipping directives, comments, literal blocks, tables and
+//! inline markup such as roles, literals and link targets.
+
+use markup::*;
+
+/// Directives whose content is an admonition.
+const ADMONITIONS: &[&str] = &[
+    "admonition",
+    "attention",
+    "caution",
+    "danger",
+    "deprecated",
+    "error",
+    "hint",
+    "important",
+    "note",
+    "seealso",
+    "tip",
+    "todo",
+    "versionadded",
+    "versionchanged",
+    "warning",
+];
+
+/// Directives whose argument is a title.
+const TITLED: &[&str] = &["admonition", "rubric", "sidebar", "topic"];
+
+/// Directives whose content is made up of ordinary blocks of prose.
+const CONTAINERS: &[&str] = &[
+    "centered",
+    "compound",
+    "container",
+    "epigraph",
+    "glossary",
+    "highlights",
+    "hlist",
+    "only",
+    "pull-quote",
+    "rubric",
+    "sidebar",
+    "topic",
+];
+
+/// The characters that section titles can be underlined with.
+const ADORNMENTS: &[u8] = b"!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Table {
+    /// A table drawn with `+`, `-` and `|`, which ends at a blank line.
+    Grid,
+    /// A table whose columns are marked by rows of `=`, which ends at a border
+    /// followed by a blank line.
+    Simple,
+}
+
+pub fn regions(text: &str) -> Vec<Region> {
+    let lines = lines(text);
+    let mut sc = Scanner::new(text, inline_holes);
+
+    // Lines indented past this column are skipped, as the content of a code
+    // block, comment or some other directive
+    let mut skip: Option<usize> = None;
+    // The column of the paragraph before, if it ended with `::`
+    let mut literal: Option<usize> = None;
+    // The column of the directive whose options are being read
+    let mut options: Option<usize> = None;
+    // The columns of the directives we're inside of, and the block of their
+    // content
+    let mut within: Vec<(usize, Block)> = Vec::new();
+    // The column that the content of the current list item starts at
+    let mut list_indent: Option<usize> = None;
+    let mut table: Option<Table> = None;
+    let mut doctest = false;
+    // The column of the lines continuing the block being read, and of the
+    // marker which started it, if any
+    let mut para_indent = 0;
+    let mut item_indent: Option<usize> = None;
+    let mut prev_blank = true;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let (lstart, lend) = lines[i];
+        let line = &text[lstart..lend];
+        i += 1;
+
+        if line.trim().is_empty() {
+            sc.flush();
+            prev_blank = true;
+            options = None;
+            doctest = false;
+            if table == Some(Table::Grid) {
+                table = None;
+            }
+            continue;
+        }
+
+        let blank_before = prev_blank;
+        prev_blank = false;
+        let indent = indentation(line);
+        let t = line.trim();
+        let tstart = lstart + (line.len() - line.trim_start().len());
+        let tend = tstart + t.len();
+        let next = lines.get(i).map(|&(s, e)| &text[s..e]);
+
+        if let Some(base) = skip {
+            if indent > base {
+                continue;
+            }
+            skip = None;
+        }
+        if let Some(base) = literal.take() {
+            if indent > base && blank_before {
+                sc.flush();
+                skip = Some(base);
+                continue;
+            }
+        }
+        if let Some(base) = options {
+            if indent > base && field_marker(t).is_some() {
+                continue;
+            }
+            options = None;
+        }
+        while within.last().is_some_and(|&(base, _)| indent <= base) {
+            within.pop();
+        }
+        if list_indent.is_some_and(|li| indent < li) {
+            list_indent = None;
+        }
+
+        let continued = sc.pending.is_some() && !blank_before
+            && (indent == para_indent || item_indent.is_some_and(|m| indent > m));
+        if !continued {
+            sc.flush();
+            item_indent = None;
+        }
+
+        match table {
+            Some(Table::Grid) => continue,
+            Some(Table::Simple) => {
+                if simple_border(t) && next.is_none_or(|n| n.trim().is_empty()) {
+                    table = None;
+                }
+                continue;
+            }
+            None => (),
+        }
+
+        if doctest {
+            continue;
+        }
+
+        // Admonitions hold everything inside of them, even lists
+        let admonition = within.last().is_some_and(|&(_, b)| b == Block::Admonition);
+        let inside = |block: Block| if admonition { Block::Admonition } else { block };
+
+        if !continued {
+            if t.starts_with("+-") || t.starts_with("+=") {
+                table = Some(Table::Grid);
+                continue;
+            }
+            if simple_border(t) && blank_before {
+                table = Some(Table::Simple);
+                continue;
+            }
+            if t.starts_with(">>>") {
+                doctest = true;
+                continue;
+            }
+        }
+
+        if t == ".." || t.starts_with(".. ") {
+            sc.flush();
+            let rest = t[2..].trim_start();
+
+            if rest.starts_with('[') {
+                // Footnotes and citations hold prose after their label
+                if let Some(close) = rest.find(']') {
+                    let body = rest[close + 1..].trim_start();
+                    within.push((indent, inside(Block::Paragraph)));
+                    if !body.is_empty() {
+                        let bstart = tend - body.len();
+                        push(&mut sc, inside(Block::Paragraph), bstart, tend, &mut literal);
+                        para_indent = bstart - lstart;
+                        item_indent = Some(indent);
+                    }
+                    continue;
+                }
+            }
+
+            match directive(rest) {
+                Some(n) => {
+                    let name = rest[..n].to_lowercase();
+                    let name = &name[..];
+                    let arg = rest[n + 2..].trim_start();
+                    let astart = tend - arg.len();
+
+                    let body = if ADMONITIONS.contains(&name) {
+                        Some(Block::Admonition)
+                    } else if CONTAINERS.contains(&name) {
+                        Some(inside(Block::Paragraph))
+                    } else {
+                        None
+                    };
+                    let argument = if TITLED.contains(&name) {
+                        Some(Block::Heading)
+                    } else if name == "centered" {
+                        Some(inside(Block::Paragraph))
+                    } else {
+                        body.filter(|&b| b == Block::Admonition)
+                    };
+
+                    if let Some(block) = argument.filter(|_| !arg.is_empty()) {
+                        push(&mut sc, block, astart, tend, &mut literal);
+                        if block == Block::Heading {
+                            sc.flush();
+                        } else {
+                            para_indent = astart - lstart;
+                            item_indent = Some(indent);
+                        }
+                    }
+                    match body {
+                        Some(block) => within.push((indent, block)),
+                        None => skip = Some(indent),
+                    }
+                    options = Some(indent);
+                }
+                // Hyperlink targets, substitution definitions and comments
+                None => skip = Some(indent),
+            }
+            continue;
+        }
+
+        if !continued && adornment(t) {
+            // A section title with an overline
+            let title = next.map(str::trim).unwrap_or("");
+            let under = lines.get(i + 1).map(|&(s, e)| text[s..e].trim());
+            if !title.is_empty() && under == Some(t) {
+                let (s, _) = lines[i];
+                let hs = s + (text[s..].len() - text[s..].trim_start().len());
+                sc.push(Block::Heading, hs, hs + title.len());
+                sc.flush();
+                i += 2;
+            }
+            // Otherwise, a transition
+            continue;
+        }
+
+        if !continued && indent == 0 {
+            // A section title with only an underline
+            let under = next.filter(|n| indentation(n) == 0).map(str::trim_end);
+            let title = under.is_some_and(|u| {
+                adornment(u) && u != "::" && (u.len() >= t.chars().count() || u.len() >= 4)
+            });
+            if title {
+                sc.push(Block::Heading, tstart, tend);
+                sc.flush();
+                i += 1;
+                continue;
+            }
+        }
+
+        if t == "|" || t.starts_with("| ") {
+            // A line block, whose lines are each kept as they are
+            let s = tstart + 1 + (t[1..].len() - t[1..].trim_start().len());
+            if s < tend {
+                push(&mut sc, inside(Block::Paragraph), s, tend, &mut literal);
+            }
+            para_indent = indent;
+            continue;
+        }
+
+        if !continued {
+            if let Some(m) = list_marker(t).or_else(|| field_marker(t)) {
+                let rest = &t[m..];
+                let item = rest.trim_start();
+                let column = indent + m + rest.len() - item.len();
+                list_indent = Some(column);
+                para_indent = column;
+                item_indent = Some(indent);
+                if !item.is_empty() {
+                    push(&mut sc, inside(Block::ListItem), tend - item.len(), tend, &mut literal);
+                }
+                continue;
+            }
+        }
+
+        let block = match within.last() {
+            _ if admonition => Block::Admonition,
+            _ if list_indent.is_some_and(|li| indent >= li) => Block::ListItem,
+            Some(&(_, block)) => block,
+            None if indent > 0 => Block::BlockQuote,
+            None => Block::Paragraph,
+        };
+        if !continued {
+            para_indent = indent;
+        }
+        push(&mut sc, block, tstart, tend, &mut literal);
+    }
+
+    sc.flush();
+    sc.res
+}
+
+/// Adds the prose of a line to the block being read. A line ending with `::`
+/// starts a literal block, and its colons are left out unless they're needed
+/// to end the sentence.
+fn push(sc: &mut Scanner, block: Block, start: usize, end: usize, literal: &mut Option<usize>) {
+    let t = &sc.text[start..end];
+    let column = start - sc.text[..start].rfind('\n').map_or(0, |i| i + 1);
+
+    let end = match t.strip_suffix("::") {
+        Some(p) => {
+            *literal = Some(column);
+            if p.is_empty() || p.ends_with([' ', '\t']) {
+                start + p.trim_end().len()
+            } else {
+                end - 1
+            }
+        }
+        None => end,
+    };
+
+    if start < end {
+        sc.push(block, start, end);
+    }
+}
+
+/// Finds the content of every line of `text`, without its line break.
+fn lines(text: &str) -> Vec<(usize, usize)> {
+    let mut res = Vec::new();
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        res.push((offset, offset + content.len()));
+        offset += line.len();
+    }
+
+    res
+}
+
+/// Returns the length of the name of the directive that `rest` starts with,
+/// as in `note:: text`, if it's a directive.
+fn directive(rest: &str) -> Option<usize> {
+    let n = rest.find("::")?;
+    let name = &rest[..n];
+    let valid = !name.is_empty() && !name.starts_with(['_', '|', '['])
+        && name.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_:+.".contains(&b));
+
+    if valid && rest[n + 2..].chars().next().is_none_or(char::is_whitespace) {
+        Some(n)
+    } else {
+        None
+    }
+}
+
+/// Whether `t` is made up of a single punctuation character, repeated.
+fn adornment(t: &str) -> bool {
+    let b = t.as_bytes();
+    b.len() >= 2 && ADORNMENTS.contains(&b[0]) && b.iter().all(|&c| c == b[0])
+}
+
+/// Whether `t` is a border of a simple table, like `=====  =====`.
+fn simple_border(t: &str) -> bool {
+    t.starts_with('=') && t.contains(' ') && t.bytes().all(|b| b == b'=' || b == b' ')
+}
+
+/// Returns the length of the list marker at the start of `t`, if there is one.
+fn list_marker(t: &str) -> Option<usize> {
+    let b = t.as_bytes();
+    let m = if t.starts_with(['-', '*', '+', '•', '‣', '⁃']) {
+        t.chars().next().unwrap().len_utf8()
+    } else {
+        let open = (b.first() == Some(&b'(')) as usize;
+        let n = b[open..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphanumeric() || **c == b'#')
+            .count();
+        let enumerator = &t[open..open + n];
+        let valid = enumerator == "#" || enumerator.bytes().all(|c| c.is_ascii_digit())
+            || (n == 1 && enumerator.bytes().all(|c| c.is_ascii_alphabetic()))
+            || enumerator.bytes().all(|c| b"ivxlcdm".contains(&c))
+            || enumerator.bytes().all(|c| b"IVXLCDM".contains(&c));
+        let close = match b.get(open + n) {
+            Some(&b')') => true,
+            Some(&b'.') => open == 0,
+            _ => false,
+        };
+        if n == 0 || !valid || !close {
+            return None;
+        }
+        open + n + 1
+    };
+
+    if m == b.len() || b[m] == b' ' || b[m] == b'\t' {
+        Some(m)
+    } else {
+        None
+    }
+}
+
+/// Returns the length of the field name at the start of `t`, as in `:param x:`,
+/// if there is one.
+fn field_marker(t: &str) -> Option<usize> {
+    let rest = t.strip_prefix(':')?;
+    let close = rest.find(':')?;
+    let b = rest.as_bytes();
+
+    if close == 0 || b[0] == b' ' || b.get(close + 1).is_some_and(|&c| c != b' ' && c != b'\t') {
+        return None;
+    }
+    Some(close + 2)
+}
+
+/// Returns the length of the role at `i`, as in `:ref:`, if there is one.
+fn role(b: &[u8], i: usize) -> Option<usize> {
+    if b.get(i) != Some(&b':') {
+        return None;
+    }
+
+    let mut j = i + 1;
+    while j < b.len() && (b[j].is_ascii_alphanumeric() || b"-_.+:".contains(&b[j])) {
+        j += 1;
+    }
+    let last = i + 1 + b[i + 1..j].iter().rposition(|&c| c == b':')?;
+    if last > i + 1 {
+        Some(last + 1 - i)
+    } else {
+        None
+    }
+}
+
+/// Finds where the title of a reference like `the title <target>` ends, if it
+/// has one, leaving out the whitespace before the target.
+fn title_end(b: &[u8]) -> Option<usize> {
+    if b.last() != Some(&b'>') {
+        return None;
+    }
+    let open = b.iter().rposition(|&c| c == b'<')?;
+    let end = b[..open]
+        .iter()
+        .rposition(|c| !c.is_ascii_whitespace())
+        .map_or(0, |k| k + 1);
+
+    if end > 0 && end < open {
+        Some(end)
+    } else {
+        None
+    }
+}
+
+/// Finds the inline markup in a block made up of `ranges`, returning the
+/// ranges of bytes which aren't prose: literals, roles and their targets,
+/// interpreted text, substitutions, footnote references, link targets and
+/// urls.
+fn inline_holes(text: &str, ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let (b, pos) = joined(text, ranges);
+    let n = b.len();
+
+    let mut holes: Vec<(usize, usize)> = Vec::new();
+    let hole = |i: usize, j: usize, holes: &mut Vec<(usize, usize)>| {
+        if i < j {
+            holes.push((pos[i], pos[j - 1] + 1));
+        }
+    };
+    let backtick = |from: usize| b[from..].iter().position(|&c| c == b'`').map(|j| from + j);
+
+    let mut i = 0;
+    while i < n {
+        // Inline markup can't start in the middle of a word
+        let start = i == 0 || !b[i - 1].is_ascii_alphanumeric();
+
+        match b[i] {
+            b'\\' if i + 1 < n => {
+                hole(i, i + 1, &mut holes);
+                i += 2;
+            }
+            b'`' if start && b[i..].starts_with(b"``") => match find(&b, i + 2, b"``") {
+                Some(j) => {
+                    hole(i, j + 2, &mut holes);
+                    i = j + 2;
+                }
+                None => i += 2,
+            },
+            b'`' if start => match backtick(i + 1) {
+                Some(j) => {
+                    let underscores = run(&b[j + 1..], b'_');
+                    let to = if (1..=2).contains(&underscores) {
+                        // A hyperlink reference, whose text is prose
+                        let cut = title_end(&b[i + 1..j]).map_or(j, |k| i + 1 + k);
+                        hole(i, i + 1, &mut holes);
+                        hole(cut, j + 1 + underscores, &mut holes);
+                        j + 1 + underscores
+                    } else {
+                        // Interpreted text, which Sphinx treats as a reference
+                        let to = j + 1 + role(&b, j + 1).unwrap_or(0);
+                        hole(i, to, &mut holes);
+                        to
+                    };
+                    i = to;
+                }
+                None => i += 1,
+            },
+            b':' if start => match role(&b, i) {
+                Some(r) if b.get(i + r) == Some(&b'`') => match backtick(i + r + 1) {
+                    Some(j) => {
+                        // Only an explicit title is prose, as in
+                        // :ref:`the title <target>`
+                        match title_end(&b[i + r + 1..j]) {
+                            Some(k) => {
+                                hole(i, i + r + 1, &mut holes);
+                                hole(i + r + 1 + k, j + 1, &mut holes);
+                            }
+                            None => hole(i, j + 1, &mut holes),
+                        }
+                        i = j + 1;
+                    }
+                    None => i += r,
+                },
+                _ => i += 1,
+            },
+            b'|' if start && b.get(i + 1).is_some_and(|c| !c.is_ascii_whitespace()) => {
+                let close = b[i + 1..]
+                    .iter()
+                    .position(|&c| c == b'|')
+                    .map(|j| i + 1 + j)
+                    .filter(|&j| !b[j - 1].is_ascii_whitespace());
+                match close {
+                    Some(j) => {
+                        let to = j + 1 + run(&b[j + 1..], b'_').min(2);
+                        hole(i, to, &mut holes);
+                        i = to;
+                    }
+                    None => i += 1,
+                }
+            }
+            b'[' if start => {
+                let close = b[i..].iter().position(|&c| c == b']').map(|j| i + j);
+                match close {
+                    Some(j) if b.get(j + 1) == Some(&b'_') => {
+                        hole(i, j + 2, &mut holes);
+                        i = j + 2;
+                    }
+                    _ => i += 1,
+                }
+            }
+            b'_' if start && b.get(i + 1) == Some(&b'`') => match backtick(i + 2) {
+                Some(j) => {
+                    // An inline target, whose text is prose
+                    hole(i, i + 2, &mut holes);
+                    hole(j, j + 1, &mut holes);
+                    i = j + 1;
+                }
+                None => i += 2,
+            },
+            b'_' if !start => {
+                // The underscores of a reference like `name_`
+                let u = run(&b[i..], b'_');
+                if u <= 2 && b.get(i + u).is_none_or(|c| !c.is_ascii_alphanumeric()) {
+                    hole(i, i + u, &mut holes);
+                }
+                i += u;
+            }
+            b'h' | b'w' if start => match url(&b, i) {
+                Some(e) => {
+                    hole(i, e, &mut holes);
+                    i = e;
+                }
+                None => i += 1,
+            },
+            _ => i += 1,
+        }
+    }
+
+    holes.sort();
+    holes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prose(text: &str) -> String {
+        mask(text, &regions(text))
+    }
+
+    fn kept(text: &str) -> Vec<&str> {
+        regions(text)
+            .iter()
+            .map(|r| &text[r.start..r.end])
+            .collect()
+    }
+
+    #[test]
+    fn rst_directives() {
+        let text = "\
+.. code-block:: python
+   :linenos:
+
+   very = 1
+
+.. note:: Be
+   careful.
+
+   Really.
+
+.. _very-target: http://very.com
+
+.. a very comment
+   continued
+
+Done.
+";
+        assert_eq!(vec!["Be", "careful.", "Really.", "Done."], kept(text));
+
+        let blocks: Vec<_> = regions(text).iter().map(|r| r.block).collect();
+        assert_eq!(Block::Admonition, blocks[2]);
+        assert_eq!(Block::Paragraph, blocks[3]);
+    }
+
+    #[test]
+    fn rst_literal_blocks() {
+        let text = "For example::\n\n    very = 1\n\nAnd ::\n\n  very\n\nMore.\n";
+        assert_eq!(vec!["For example:", "And", "More."], kept(text));
+    }
+
+    #[test]
+    fn rst_blocks() {
+        let text = "\
+=====
+Title
+=====
+
+Section
+-------
+
+- item one
+  continued
+- item two
+
+  quoted
+
+:param x: the value
+
++-----+
+| very |
++-----+
+";
+        let blocks: Vec<_> = regions(text)
+            .iter()
+            .map(|r| (&text[r.start..r.end], r.block))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("Title", Block::Heading),
+                ("Section", Block::Heading),
+                ("item one", Block::ListItem),
+                ("continued", Block::ListItem),
+                ("item two", Block::ListItem),
+                ("quoted", Block::ListItem),
+                ("the value", Block::ListItem),
+            ],
+            blocks
+        );
+
+        let groups: Vec<_> = regions(text).iter().map(|r| r.group).collect();
+        assert_eq!(vec![0, 1, 2, 2, 3, 4, 5], groups);
+    }
+
+    #[test]
+    fn rst_inline() {
+        let text = "Use ``very``, :func:`very`, :ref:`the docs <very>`, `a link <http://x>`_, \
+                    |very| and [#]_ x_.\n";
+        assert_eq!(
+            "Use         ,             ,       the docs        ,  a link             ,        \
+             and      x .\n",
+            prose(text)
+        );
+    }
+
+    #[test]
+    fn rst_offsets() {
+        let text = "A\n=\n\n``x`` very\n";
+        let masked = prose(text);
+        assert_eq!(text.len(), masked.len());
+        assert_eq!(text.find("very"), masked.find("very"));
+    }
+}
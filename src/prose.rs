@@ -1,16 +1,24 @@
 use failure::Error;
 use memchr::memchr;
-use ordermap::OrderSet;
 use rayon::prelude::*;
-use regex::{RegexBuilder, RegexSetBuilder};
+use regex::Regex;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::cmp;
 use strfmt::strfmt;
 
+use check::*;
+use fix::preserve_case;
 use lint::*;
+use linter::*;
+use markup::*;
+use readability::Stats;
+use reflow::Reflowed;
+use scope::spans;
+use suppress::*;
 use util::*;
 
-#[derive(Clone, Copy, Debug, Serialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
 pub struct Offset {
     pub start: usize,
     pub end: usize,
@@ -25,6 +33,13 @@ pub struct Match {
     pub severity: Severity,
     pub msg: String,
     pub offset: Offset,
+    /// The text that was matched.
+    pub text: String,
+    /// Suggested replacements for the matched text, best first.
+    pub replacements: Vec<String>,
+    /// Words the matched text may have been meant to be, best first. Unlike
+    /// `replacements`, these are only guesses, so they're never applied.
+    pub suggestions: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -32,8 +47,19 @@ pub struct Prose<'a> {
     pub name: &'a str,
     pub text: &'a str,
     pub split: usize,
-    pub unicode: bool,
+    /// The number of lines each partition is extended by when a lint can match
+    /// an unbounded number of line breaks. Otherwise, partitions are extended
+    /// just far enough to catch every match.
+    pub overlap: usize,
     pub eol: u8,
+    /// The markup language of `text`, which decides which parts of it are
+    /// linted.
+    pub format: Format,
+    /// Whether suppression directives which don't suppress any matches should
+    /// be reported as matches themselves.
+    pub unused_suppressions: bool,
+    /// The settings of the front-end for `format`.
+    pub markup: &'a Options,
 }
 
 impl<'a> Prose<'a> {
@@ -41,7 +67,7 @@ impl<'a> Prose<'a> {
     pub fn pos(&self, offset: usize, clens: &[usize], start: usize) -> (usize, usize) {
         let offset = offset + clens[start];
 
-        let linum = lines(self.text[clens[start]..offset].as_bytes(), self.eol) + start;
+        let linum = lines(&self.text.as_bytes()[clens[start]..offset], self.eol) + start;
 
         (linum, offset - clens[linum - 1] + 1)
     }
@@ -54,13 +80,13 @@ impl<'a> Prose<'a> {
     // the last item will be equal to the length of the whole string
     pub fn line_lengths(&self) -> Vec<usize> {
         let nlines = lines(self.text.as_bytes(), self.eol);
-        let mut lengths: Vec<usize> = Vec::with_capacity(nlines as usize + 2);
+        let mut lengths: Vec<usize> = Vec::with_capacity(nlines + 2);
 
         lengths.push(0);
 
         let mut current_byte = 0;
 
-        while let Some(pos) = memchr(self.eol, self.text[current_byte..].as_bytes()) {
+        while let Some(pos) = memchr(self.eol, &self.text.as_bytes()[current_byte..]) {
             lengths.push(current_byte + pos + 1);
             current_byte = current_byte + pos + 1;
         }
@@ -73,7 +99,30 @@ impl<'a> Prose<'a> {
         lengths
     }
 
-    pub fn lint(&self, lints: &[Lint]) -> Result<Vec<Match>, Error> {
+    /// Finds the regions of prose in the text, and blanks out everything else,
+    /// keeping all offsets intact.
+    fn masked(&self) -> (Option<Vec<Region>>, Cow<'a, str>) {
+        let regions = regions(self.format, self.text, self.markup);
+        let text = match regions {
+            Some(ref regions) => Cow::Owned(mask(self.text, regions)),
+            None => Cow::Borrowed(self.text),
+        };
+
+        (regions, text)
+    }
+
+    /// Counts the sentences, words and syllables of the prose in the text.
+    pub fn stats(&self) -> Stats {
+        let (regions, text) = self.masked();
+        let paragraphs = spans(Scope::Paragraph, &text, regions.as_ref().map(|r| &r[..]));
+
+        Stats::new(&text, &paragraphs)
+    }
+
+    pub fn lint(&self, linter: &Linter) -> Result<Vec<Match>, Error> {
+        let (regions, masked) = self.masked();
+        let text = &masked[..];
+
         let line_lengths = self.line_lengths();
         let nlines = line_lengths.len() - 1;
         let split = cmp::min(nlines, self.split);
@@ -92,165 +141,848 @@ impl<'a> Prose<'a> {
         // the right end
         bytes.push(*line_lengths.last().unwrap() + 1);
 
-        (0..cmp::max((bytes.len() - 1), 1))
+        // Each partition is searched along with the lines after it that a
+        // match starting inside of it could reach, so that no match is lost
+        let overlap = linter.newlines.unwrap_or(self.overlap);
+
+        let parts = (0..cmp::max(bytes.len() - 1, 1))
             .into_par_iter()
             .map(|s| {
-                let buf = if bytes[s] < self.text.len() {
-                    if bytes[s + 1] < self.text.len() {
-                        &self.text[bytes[s]..bytes[s + 1]]
-                    } else {
-                        &self.text[bytes[s]..]
-                    }
+                let own = bytes[s + 1];
+                let end = if own < text.len() {
+                    line_lengths[cmp::min((s + 1) * lps + overlap, nlines)]
+                } else {
+                    text.len()
+                };
+                let buf = if bytes[s] < text.len() {
+                    &text[bytes[s]..end]
                 } else {
                     ""
                 };
 
-                let mut regexes: OrderSet<String> = OrderSet::new();
-                let _ = lints.iter().map(|x| {
-                    regexes.extend(
-                        x.mapping
-                            .iter()
-                            .filter(|&(_, v)| v.is_some())
-                            .map(|x| x.0.clone()),
-                    )
-                });
-
-                let res1 = lints
-                    .into_par_iter()
-                    .map(|lint| -> Result<Vec<Match>, Error> {
-                        let rs: Vec<&str> = lint.mapping
-                            .iter()
-                            .filter(|x| x.1.is_none())
-                            .map(|x| &x.0[..])
-                            .collect();
-                        if rs.is_empty() {
-                            return Ok(Vec::new());
-                        }
-
-                        let len = rs.len();
-                        let rps = self.regexes_per_partition(len);
-                        let partitions = (len as f64 / rps as f64).ceil() as usize;
-                        (0..partitions)
-                            .into_par_iter()
-                            .map(|i| i * rps)
-                            .map(|ro| -> Result<Vec<Match>, Error> {
-                                let mut ires = Vec::new();
-                                let len = rs.len();
-                                let slice = if ro + rps >= len {
-                                    &rs[ro..]
-                                } else {
-                                    &rs[ro..ro + rps]
-                                };
-
-                                let start = format!("(?:{})", rs[0].clone());
-
-                                let regex =
-                                    slice.iter().fold(start, |acc, s| acc + "|(?:" + s + ")");
-
-                                let regex =
-                                    RegexBuilder::new(&regex).unicode(self.unicode).build()?;
-                                if regex.is_match(buf) {
-                                    let msg = &lint.msg[..];
-                                    let name = &lint.name[..];
-
-                                    for mat in regex.find_iter(buf) {
-                                        let (l, c) = self.pos(mat.start(), &line_lengths, s * lps);
-                                        let off = Offset {
-                                            start: bytes[s] + mat.start(),
-                                            end: bytes[s] + mat.end(),
-                                        };
-                                        let mut map = HashMap::new();
-                                        map.insert(
-                                            "match".to_string(),
-                                            &buf[mat.start()..mat.end()],
-                                        );
-
-                                        ires.push(Match {
-                                            file: String::from(self.name),
-                                            line: l,
-                                            column: c,
-                                            lint: String::from(name),
-                                            severity: lint.severity,
-                                            msg: strfmt(msg, &map)
-                                                .unwrap_or_else(|_| String::from(msg)),
-                                            offset: off,
-                                        });
-                                    }
-                                }
-                                Ok(ires)
-                            })
-                            .reduce(|| Ok(Vec::new()), bind_extend)
-                    })
-                    .reduce(|| Ok(Vec::new()), bind_extend);
-
-                let set = RegexSetBuilder::new(&regexes)
-                    .unicode(self.unicode)
-                    .build()?;
-                let matches: Vec<usize> = set.matches(buf).into_iter().collect();
-
-                let res2 = matches
-                    .par_iter()
-                    .map(|rix| -> Result<Vec<Match>, Error> {
-                        let regex = regexes.get_index(*rix).unwrap();
-                        let r = RegexBuilder::new(regex).unicode(self.unicode).build()?;
-                        let mut ires = Vec::new();
-                        for mat in r.find_iter(buf) {
-                            for lint in lints {
-                                if let Some(&Some(ref v)) = lint.mapping.get(regex) {
-                                    let msg_mapping = &lint.msg_mapping[..];
-                                    let name = &lint.name[..];
-                                    let (l, c) = self.pos(mat.start(), &line_lengths, s * lps);
-                                    let bo = Offset {
-                                        start: bytes[s] + mat.start(),
-                                        end: bytes[s] + mat.end(),
-                                    };
-                                    let mut map = HashMap::new();
-                                    map.insert("match".to_string(), &buf[mat.start()..mat.end()]);
-                                    map.insert("value".to_string(), v);
-
-                                    ires.push(Match {
-                                        file: String::from(self.name),
-                                        line: l,
-                                        column: c,
-                                        lint: String::from(name),
-                                        severity: lint.severity,
-                                        msg: strfmt(msg_mapping, &map)
-                                            .unwrap_or_else(|_| v.clone()),
-                                        offset: bo,
-                                    });
-                                }
-                            }
-                        }
-                        Ok(ires)
+                // Matches starting in the overlap belong to the next partition
+                Ok(search(linter, buf, false)
+                    .into_iter()
+                    .filter(|(_, h)| bytes[s] + h.start < own)
+                    .map(|(pattern, mut h)| {
+                        h.start += bytes[s];
+                        h.end += bytes[s];
+                        (pattern, h)
                     })
-                    .reduce(|| Ok(Vec::new()), bind_extend);
+                    .collect())
+            })
+            .reduce(|| Ok(Vec::new()), bind_extend)?;
+
+        // The matches of a regex don't overlap, so one which runs past the end
+        // of its partition hides those of the same regex which start under it
+        let mut reached: HashMap<Pattern, usize> = HashMap::new();
+        let hits = parts.into_iter().filter_map(|(pattern, h)| {
+            if reached.get(&pattern).is_some_and(|&end| h.start < end) {
+                return None;
+            }
+            reached.insert(pattern, h.end);
+            Some(h)
+        });
+        let mut matches: Vec<(Hit, Offset)> = except(linter, text, hits.collect())
+            .into_iter()
+            .map(|h| {
+                let offset = Offset {
+                    start: h.start,
+                    end: h.end,
+                };
+                (h, offset)
+            })
+            .collect();
+
+        if linter.reflowed {
+            let paragraphs = paragraphs(text, regions.as_ref().map(|r| &r[..]));
+            let reflowed = Reflowed::new(text, &paragraphs);
+
+            let hits = search(linter, &reflowed.text, true)
+                .into_iter()
+                .map(|(_, h)| h)
+                .collect();
+            let hits = except(linter, &reflowed.text, hits);
+            matches.extend(hits.into_iter().map(|h| {
+                let offset = Offset {
+                    start: reflowed.original(h.start),
+                    end: reflowed.original(h.end),
+                };
+                (h, offset)
+            }));
+        }
 
-                let mut nm = bind_extend(res1, res2)?;
+        // Masked markup is blank, but a phrase running across a code span or
+        // the like isn't in the prose
+        let mut matches: Vec<Match> = matches
+            .into_iter()
+            .filter(|&(_, o)| !crosses_hole(regions.as_ref().map(|r| &r[..]), o.start, o.end))
+            .map(|(h, o)| self.matched(linter, h, locate(o.start, &line_lengths), o))
+            .collect();
+
+        matches.par_sort_unstable_by(|x, y| {
+            (x.offset.start, x.offset.end, &x.lint).cmp(&(y.offset.start, y.offset.end, &y.lint))
+        });
+        matches.dedup_by(|x, y| x.offset == y.offset && x.lint == y.lint);
+
+        let matches = self.aggregate(
+            linter,
+            matches,
+            text,
+            regions.as_ref().map(|r| &r[..]),
+            &line_lengths,
+        );
+
+        Ok(self.suppress(matches, &line_lengths))
+    }
 
-                nm.par_sort_unstable_by(|x, y| {
-                    if x.line.cmp(&y.line) == cmp::Ordering::Equal {
-                        x.column.cmp(&y.column)
-                    } else {
-                        x.line.cmp(&y.line)
+    /// Replaces the matches of lints which look at all of their matches
+    /// together with what those lints report.
+    fn aggregate(
+        &self,
+        linter: &Linter,
+        matches: Vec<Match>,
+        text: &str,
+        regions: Option<&[Region]>,
+        line_lengths: &[usize],
+    ) -> Vec<Match> {
+        let grouped: Vec<(usize, &Lint)> = linter
+            .lints
+            .iter()
+            .map(|cl| &cl.lint)
+            .enumerate()
+            .filter(|&(_, lint)| lint.kind != Kind::Existence)
+            .collect();
+        if grouped.is_empty() {
+            return matches;
+        }
+
+        let (mut res, rest): (Vec<Match>, Vec<Match>) = matches
+            .into_iter()
+            .partition(|m| grouped.iter().all(|&(_, lint)| lint.name != m.lint));
+        let mut scopes = HashMap::new();
+
+        for (ix, lint) in grouped {
+            let ms: Vec<&Match> = rest.iter().filter(|m| m.lint == lint.name).collect();
+            let cl = &linter.lints[ix];
+            let parts = scopes
+                .entry(lint.scope)
+                .or_insert_with(|| spans(lint.scope, text, regions));
+            let hits = match lint.kind {
+                Kind::Occurrence => occurrence(ix, lint, text, &ms, parts),
+                Kind::Consistency => consistency(ix, cl, &ms, parts),
+                Kind::Repetition => repetition(ix, cl, text, regions, parts),
+                Kind::Conditional => conditional(ix, cl, text),
+                Kind::Capitalization => capitalization(ix, cl, self.text, text, parts),
+                Kind::Spelling => spelling(ix, cl, text, parts),
+                Kind::Metric => {
+                    let paragraphs = spans(Scope::Paragraph, text, regions);
+                    metric(ix, lint, text, &paragraphs, parts)
+                }
+                Kind::Length => {
+                    let paragraphs = spans(Scope::Paragraph, text, regions);
+                    length(ix, lint, text, &paragraphs)
+                }
+                Kind::Existence => unreachable!(),
+            };
+
+            res.extend(hits.into_iter().map(|h| {
+                let offset = Offset {
+                    start: h.start,
+                    end: h.end,
+                };
+                self.matched(linter, h, locate(offset.start, line_lengths), offset)
+            }));
+        }
+
+        res.sort_by(|x, y| {
+            (x.offset.start, x.offset.end, &x.lint).cmp(&(y.offset.start, y.offset.end, &y.lint))
+        });
+        res
+    }
+
+    /// Places a hit of one of `linter`'s lints in this document. Masking keeps
+    /// offsets intact, so the matched text is taken from the original.
+    fn matched(
+        &self,
+        linter: &Linter,
+        hit: Hit,
+        (line, column): (usize, usize),
+        offset: Offset,
+    ) -> Match {
+        let lint = &linter.lints[hit.lint].lint;
+
+        Match {
+            file: String::from(self.name),
+            line,
+            column,
+            lint: lint.name.clone(),
+            severity: lint.severity,
+            msg: hit.msg,
+            offset,
+            text: String::from(&self.text[offset.start..offset.end]),
+            replacements: hit.replacements,
+            suggestions: hit.suggestions,
+        }
+    }
+
+    /// Drops the matches which are turned off by inline directives, reporting
+    /// the directives which didn't turn anything off if asked to.
+    fn suppress(&self, matches: Vec<Match>, line_lengths: &[usize]) -> Vec<Match> {
+        let suppressions = suppressions(self.text, line_lengths);
+        if suppressions.is_empty() {
+            return matches;
+        }
+
+        let mut used = vec![false; suppressions.len()];
+        let mut matches: Vec<Match> = matches
+            .into_iter()
+            .filter(|m| {
+                let mut keep = true;
+                for (i, s) in suppressions.iter().enumerate() {
+                    if s.covers(&m.lint, m.offset.start) {
+                        used[i] = true;
+                        keep = false;
                     }
+                }
+                keep
+            })
+            .collect();
+
+        if self.unused_suppressions {
+            for (s, _) in suppressions.iter().zip(used).filter(|&(_, u)| !u) {
+                let (start, end) = s.directive;
+                let (l, c) = self.pos(start, line_lengths, 0);
+
+                matches.push(Match {
+                    file: String::from(self.name),
+                    line: l,
+                    column: c,
+                    lint: String::from(UNUSED_SUPPRESSION),
+                    severity: Severity::Warning,
+                    msg: String::from("This directive doesn't suppress any matches"),
+                    offset: Offset { start, end },
+                    text: String::from(&self.text[start..end]),
+                    replacements: Vec::new(),
+                    suggestions: Vec::new(),
                 });
+            }
+
+            matches.sort_by_key(|m| (m.line, m.column));
+        }
+
+        matches
+    }
+}
 
-                Ok(nm)
+/// The regex of a linter which found a hit: a token of one of its lints, or
+/// one of its mapped patterns.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Pattern {
+    Token(usize, usize),
+    Mapped(usize),
+}
+
+/// Runs the lints of `linter` over `buf`; either those which are run over
+/// reflowed paragraphs, or the rest of them.
+fn search(linter: &Linter, buf: &str, reflowed: bool) -> Vec<(Pattern, Hit)> {
+    let res1 = linter
+        .lints
+        .par_iter()
+        .enumerate()
+        .filter(|&(_, cl)| cl.lint.normalize_whitespace == reflowed)
+        .flat_map(|(ix, cl)| {
+            cl.tokens
+                .par_iter()
+                .enumerate()
+                .map(move |(t, regex)| (ix, cl, t, regex))
+        })
+        .flat_map(|(ix, cl, t, regex)| {
+            let msg = &cl.lint.msg[..];
+
+            captures(regex, buf)
+                .into_iter()
+                .map(|(start, end, map)| {
+                    let hit = Hit {
+                        lint: ix,
+                        start,
+                        end,
+                        msg: strfmt(msg, &map).unwrap_or_else(|_| String::from(msg)),
+                        replacements: Vec::new(),
+                        suggestions: Vec::new(),
+                    };
+                    (Pattern::Token(ix, t), hit)
+                })
+                .collect::<Vec<_>>()
+        });
+
+    let hits: Vec<usize> = linter
+        .set
+        .matches(buf)
+        .into_iter()
+        .filter(|&rix| {
+            linter.lints[linter.mapped[rix].lint].lint.normalize_whitespace == reflowed
+        })
+        .collect();
+
+    let res2 = hits.par_iter().flat_map(|&rix| {
+        let mapped = &linter.mapped[rix];
+        let msg_mapping = &linter.lints[mapped.lint].lint.msg_mapping[..];
+
+        captures(&mapped.regex, buf)
+            .into_iter()
+            .map(|(start, end, map)| {
+                // Values can refer to the groups of their pattern, too
+                let value = strfmt(&mapped.value, &map).unwrap_or_else(|_| mapped.value.clone());
+                let mut map: HashMap<String, &str> = map;
+                map.insert("value".to_string(), &value);
+
+                let hit = Hit {
+                    lint: mapped.lint,
+                    start,
+                    end,
+                    msg: strfmt(msg_mapping, &map).unwrap_or_else(|_| value.clone()),
+                    replacements: vec![preserve_case(&buf[start..end], &value)],
+                    suggestions: Vec::new(),
+                };
+                (Pattern::Mapped(rix), hit)
             })
-            .reduce(|| Ok(Vec::new()), bind_extend)
+            .collect::<Vec<_>>()
+    });
+
+    res1.chain(res2).collect()
+}
+
+/// Drops the `hits` in `buf` which lie inside of an exception of their lint.
+fn except(linter: &Linter, buf: &str, mut hits: Vec<Hit>) -> Vec<Hit> {
+    let mut excepted: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+    for h in &hits {
+        let cl = &linter.lints[h.lint];
+        if cl.exceptions.is_some() {
+            excepted.entry(h.lint).or_insert_with(|| cl.excepted(buf));
+        }
     }
+    if !excepted.is_empty() {
+        hits.retain(|h| {
+            excepted.get(&h.lint).is_none_or(|spans| {
+                !spans.iter().any(|&(s, e)| s <= h.start && h.end <= e)
+            })
+        });
+    }
+
+    hits
+}
 
-    fn regexes_per_partition(&self, regexes: usize) -> usize {
-        let regexes = regexes as f64;
-        ((15000.0 / regexes) + (regexes / 10.0)).ceil() as usize
+/// Finds every match of `regex` in `buf`, along with the values that messages
+/// can refer to: `match`, and each capture group by its number and its name.
+/// Groups which didn't take part in a match are empty.
+fn captures<'t>(regex: &Regex, buf: &'t str) -> Vec<(usize, usize, HashMap<String, &'t str>)> {
+    if regex.captures_len() == 1 {
+        return regex
+            .find_iter(buf)
+            .map(|mat| {
+                let mut map = HashMap::new();
+                map.insert("match".to_string(), mat.as_str());
+                (mat.start(), mat.end(), map)
+            })
+            .collect();
     }
+
+    let names: Vec<Option<&str>> = regex.capture_names().collect();
+    regex
+        .captures_iter(buf)
+        .map(|caps| {
+            let mat = caps.get(0).unwrap();
+            let mut map = HashMap::new();
+            map.insert("match".to_string(), mat.as_str());
+            for (i, name) in names.iter().enumerate().skip(1) {
+                let group = caps.get(i).map_or("", |m| m.as_str());
+                map.insert(i.to_string(), group);
+                if let Some(name) = *name {
+                    map.insert(name.to_string(), group);
+                }
+            }
+            (mat.start(), mat.end(), map)
+        })
+        .collect()
+}
+
+/// Finds the line and column of the byte at `offset`, given the starting
+/// offset of each line.
+fn locate(offset: usize, line_lengths: &[usize]) -> (usize, usize) {
+    let line = match line_lengths.binary_search(&offset) {
+        Ok(i) => i + 1,
+        Err(i) => i,
+    };
+
+    (line, offset - line_lengths[line - 1] + 1)
 }
 
-fn bind_extend(
-    a: Result<Vec<Match>, Error>,
-    b: Result<Vec<Match>, Error>,
-) -> Result<Vec<Match>, Error> {
-    bind(a, b, |a, b| {
-        a.iter().chain(b.iter()).cloned().collect::<Vec<_>>()
+fn bind_extend<T>(a: Result<Vec<T>, Error>, b: Result<Vec<T>, Error>) -> Result<Vec<T>, Error> {
+    bind(a, b, |mut a, b| {
+        a.extend(b);
+        a
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ordermap::OrderMap;
+    use readability::Metric;
+
+    fn linter(mapping: &[(&str, Option<&str>)]) -> Linter {
+        let mut map = OrderMap::new();
+        for &(k, v) in mapping {
+            map.insert(String::from(k), v.map(String::from));
+        }
+
+        let lint = Lint {
+            name: String::from("temper.test"),
+            severity: Severity::Warning,
+            msg: String::from("{match} is a usage error"),
+            msg_mapping: String::from("Consider replacing {match} with {value}"),
+            mapping: map,
+            ..Lint::default()
+        };
+
+        Linter::new(vec![lint], true).unwrap()
+    }
+
+    lazy_static! {
+        static ref MARKUP: Options = Options::default();
+    }
+
+    fn prose(text: &str) -> Prose<'_> {
+        Prose {
+            name: "test",
+            text,
+            split: 1,
+            overlap: 0,
+            eol: b'\n',
+            format: Format::Text,
+            unused_suppressions: false,
+            markup: &MARKUP,
+        }
+    }
+
+    #[test]
+    fn prose_lint_tokens_and_mapping() {
+        let linter = linter(&[(r"\bvery\b", None), (r"\butilize\b", Some("use"))]);
+        let matches = prose("This is very nice.\nI utilize it.\n")
+            .lint(&linter)
+            .unwrap();
+
+        assert_eq!(2, matches.len());
+        assert_eq!((1, 9), (matches[0].line, matches[0].column));
+        assert_eq!("very is a usage error", matches[0].msg);
+        assert_eq!((2, 3), (matches[1].line, matches[1].column));
+        assert_eq!("Consider replacing utilize with use", matches[1].msg);
+        assert_eq!("utilize", matches[1].text);
+        assert_eq!(vec![String::from("use")], matches[1].replacements);
+    }
+
+    #[test]
+    fn prose_lint_captures() {
+        let lint = Lint {
+            name: String::from("temper.test"),
+            msg: String::from("{word}, then {2}"),
+            msg_mapping: String::from("{1}: {value}"),
+            mapping: vec![
+                (String::from(r"\b(?P<word>[A-Z]\w+) (\w+)\b"), None),
+                (String::from(r"\bbased on (\w+)\b"), Some(String::from("{1}-based"))),
+            ].into_iter()
+                .collect(),
+            ..Lint::default()
+        };
+        let linter = Linter::new(vec![lint], true).unwrap();
+        let matches = prose("Zero based on one.\n").lint(&linter).unwrap();
+
+        let msgs: Vec<_> = matches.iter().map(|m| &m.msg[..]).collect();
+        assert_eq!(vec!["Zero, then based", "one: one-based"], msgs);
+        assert_eq!(vec![String::from("one-based")], matches[1].replacements);
+    }
+
+    #[test]
+    fn prose_lint_split() {
+        let linter = linter(&[(r"\bin\s?order\s?to\b", None), (r"\bvery\b", None)]);
+        let text = "very\nin order\nto\nvery in\norder to\nvery\nvery very\n";
+        let whole = prose(text).lint(&linter).unwrap();
+
+        assert_eq!(Some(1), linter.newlines.map(|n| n / 2));
+        assert_eq!(7, whole.len());
+        for split in 2..8 {
+            let matches = Prose {
+                split,
+                ..prose(text)
+            }.lint(&linter)
+                .unwrap();
+
+            let offsets: Vec<_> = matches.iter().map(|m| m.offset).collect();
+            assert_eq!(whole.iter().map(|m| m.offset).collect::<Vec<_>>(), offsets);
+        }
+
+        // A match running into the next partition hides the overlapping one
+        // which that partition would find on its own
+        let chained = self::linter(&[(r"a\sb", None), (r"b\sc", None)]);
+        let text = "a\nb\nc\nd\n";
+        for split in 1..5 {
+            let matches = Prose {
+                split,
+                ..prose(text)
+            }.lint(&chained)
+                .unwrap();
+
+            let found: Vec<_> = matches.iter().map(|m| &m.text[..]).collect();
+            assert_eq!(vec!["a\nb"], found, "split {}", split);
+        }
+    }
+
+    #[test]
+    fn prose_lint_normalize_whitespace() {
+        let lint = Lint {
+            name: String::from("temper.test"),
+            msg: String::from("{match}"),
+            mapping: vec![(String::from(r"\b[Ii]n order to\b"), None)]
+                .into_iter()
+                .collect(),
+            normalize_whitespace: true,
+            ..Lint::default()
+        };
+        let linter = Linter::new(vec![lint], true).unwrap();
+        let text = "- We did it in order\n  to win.\n- In order\n- to lose.\n\nIn\norder\n\nto\n";
+        let matches = Prose {
+            format: Format::Markdown,
+            ..prose(text)
+        }.lint(&linter)
+            .unwrap();
+
+        assert_eq!(1, matches.len());
+        assert_eq!((1, 13), (matches[0].line, matches[0].column));
+        assert_eq!("in order\n  to", matches[0].text);
+        assert_eq!("in order to", matches[0].msg);
+    }
+
+    #[test]
+    fn prose_lint_normalize_whitespace_cuts() {
+        let lint = Lint {
+            name: String::from("temper.test"),
+            msg: String::from("{match}"),
+            mapping: vec![(String::from(r"\b[Ii]n\s+order\s+to\b"), None)]
+                .into_iter()
+                .collect(),
+            normalize_whitespace: true,
+            ..Lint::default()
+        };
+        let linter = Linter::new(vec![lint], true).unwrap();
+        let text = "We did it in order `x` to win, in\norder to\nlose.\n";
+        let matches = Prose {
+            format: Format::Markdown,
+            ..prose(text)
+        }.lint(&linter)
+            .unwrap();
+
+        // A code span between the words isn't whitespace
+        assert_eq!(1, matches.len());
+        assert_eq!("in\norder to", matches[0].text);
+    }
+
+    #[test]
+    fn prose_lint_exceptions() {
+        let lint = Lint {
+            name: String::from("temper.test"),
+            msg: String::from("{match}"),
+            mapping: vec![(String::from(r"(?i:\bvery\b)"), None)]
+                .into_iter()
+                .collect(),
+            exceptions: vec![String::from(r"(?i:\bvery well\b)")],
+            ..Lint::default()
+        };
+        let linter = Linter::new(vec![lint], true).unwrap();
+        let matches = prose("Very good.\nVery well, very\nwell.\n")
+            .lint(&linter)
+            .unwrap();
+
+        let found: Vec<_> = matches.iter().map(|m| (m.line, m.column)).collect();
+        assert_eq!(vec![(1, 1), (2, 12)], found);
+    }
+
+    #[test]
+    fn prose_lint_occurrence() {
+        let lint = Lint {
+            name: String::from("temper.test.bangs"),
+            kind: Kind::Occurrence,
+            msg: String::from("{count} in this {scope}"),
+            mapping: vec![(String::from("!"), None)].into_iter().collect(),
+            scope: Scope::Paragraph,
+            max: Some(1.0),
+            ..Lint::default()
+        };
+        let linter = Linter::new(vec![lint], true).unwrap();
+        let matches = prose("Wow! Great!\nYes!\n\nFine!\n").lint(&linter).unwrap();
+
+        assert_eq!(1, matches.len());
+        assert_eq!((1, 11), (matches[0].line, matches[0].column));
+        assert_eq!("3 in this paragraph", matches[0].msg);
+
+        let unbounded = Lint {
+            kind: Kind::Occurrence,
+            ..Lint::default()
+        };
+        assert!(Linter::new(vec![unbounded], true).is_err());
+    }
+
+    #[test]
+    fn prose_lint_consistency() {
+        let groups = vec![
+            vec![String::from(r"(?i:\bemail\b)"), String::from(r"(?i:\be-mail\b)")],
+            vec![String::from(r"\bcolor\b"), String::from(r"\bcolour\b")],
+        ];
+        let lint = Lint {
+            name: String::from("temper.test"),
+            kind: Kind::Consistency,
+            msg: String::from("{match} -> {value}"),
+            mapping: groups.iter().flatten().map(|g| (g.clone(), None)).collect(),
+            groups,
+            ..Lint::default()
+        };
+        let linter = Linter::new(vec![lint], true).unwrap();
+        let matches = prose("E-mail the colour.\nSend email, then email.\nA color.\n")
+            .lint(&linter)
+            .unwrap();
+
+        let found: Vec<_> = matches.iter().map(|m| &m.msg[..]).collect();
+        assert_eq!(vec!["E-mail -> email", "color -> colour"], found);
+        assert_eq!(vec![String::from("Email")], matches[0].replacements);
+    }
+
+    #[test]
+    fn prose_lint_repetition() {
+        let lint = Lint {
+            name: String::from("temper.test.repetition"),
+            kind: Kind::Repetition,
+            msg: String::from("{match} is repeated"),
+            exceptions: vec![String::from(r"\bhad had\b")],
+            ..Lint::default()
+        };
+        let linter = Linter::new(vec![lint], true).unwrap();
+        let text = "This is the\nThe end. He had had it, it\nworks. No. No it is it  it.\n";
+        let matches = prose(text).lint(&linter).unwrap();
+
+        let found: Vec<_> = matches
+            .iter()
+            .map(|m| (m.line, m.column, &m.text[..], &m.replacements[0][..]))
+            .collect();
+        assert_eq!(
+            vec![
+                (1, 9, "the\nThe", "the"),
+                (2, 21, "it, it", "it,"),
+                (3, 21, "it  it", "it"),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn prose_lint_repetition_markdown() {
+        let lint = Lint {
+            name: String::from("temper.test.repetition"),
+            kind: Kind::Repetition,
+            ..Lint::default()
+        };
+        let linter = Linter::new(vec![lint], true).unwrap();
+
+        for text in &[
+            "Set the `x` the value.\n",
+            "See [the](http://a.com) the docs.\n",
+        ] {
+            let matches = Prose {
+                format: Format::Markdown,
+                ..prose(text)
+            }.lint(&linter)
+                .unwrap();
+            assert!(matches.is_empty(), "{:?}", matches);
+            assert_eq!(*text, ::fix::apply(text, &matches).text);
+        }
+
+        let text = "Set the\n  the *value*.\n";
+        let matches = Prose {
+            format: Format::Markdown,
+            ..prose(text)
+        }.lint(&linter)
+            .unwrap();
+        assert_eq!("Set the *value*.\n", ::fix::apply(text, &matches).text);
+    }
+
+    #[test]
+    fn prose_lint_conditional() {
+        let lint = Lint {
+            name: String::from("temper.test.acronyms"),
+            kind: Kind::Conditional,
+            msg: String::from("{match} is used before it's defined"),
+            exceptions: vec![String::from(r"\bAPI\b")],
+            first: Some(String::from(r"\b[A-Z]{3,5}\b")),
+            second: Some(String::from(r"(?:\b[A-Z][a-z]+\s+)+\(([A-Z]{3,5})\)")),
+            ..Lint::default()
+        };
+        let linter = Linter::new(vec![lint], true).unwrap();
+        let text = "The TLS and API docs.\nUse Transport Layer\nSecurity (TLS) and\n\
+                    DNS, then TLS.\n";
+        let matches = prose(text).lint(&linter).unwrap();
+
+        let found: Vec<_> = matches.iter().map(|m| (m.line, &m.text[..])).collect();
+        assert_eq!(vec![(1, "TLS"), (4, "DNS")], found);
+
+        let half = Lint {
+            kind: Kind::Conditional,
+            first: Some(String::from("x")),
+            ..Lint::default()
+        };
+        assert!(Linter::new(vec![half], true).is_err());
+    }
+
+    #[test]
+    fn prose_lint_capitalization() {
+        let lint = |style| Lint {
+            name: String::from("temper.test.headings"),
+            kind: Kind::Capitalization,
+            msg: String::from("{value}"),
+            exceptions: vec![String::from(r"\bRust\b")],
+            scope: Scope::Heading,
+            style,
+            ..Lint::default()
+        };
+        let text = "# Using `Foo` In Rust With the API\n\nSome Text.\n\n## what I said\n";
+        let lint_with = |style| {
+            let linter = Linter::new(vec![lint(style)], true).unwrap();
+            Prose {
+                format: Format::Markdown,
+                ..prose(text)
+            }.lint(&linter)
+                .unwrap()
+                .into_iter()
+                .map(|m| (m.line, m.replacements[0].clone()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec![
+                (1, String::from("Using `Foo` in Rust with the API")),
+                (5, String::from("What I said")),
+            ],
+            lint_with(Case::Sentence)
+        );
+        assert_eq!(
+            vec![
+                (1, String::from("Using `Foo` in Rust with the API")),
+                (5, String::from("What I Said")),
+            ],
+            lint_with(Case::Title)
+        );
+    }
+
+    #[test]
+    fn prose_lint_metric() {
+        let lint = Lint {
+            name: String::from("temper.test.grade"),
+            kind: Kind::Metric,
+            msg: String::from("{metric} {score}"),
+            scope: Scope::Paragraph,
+            metric: Some(Metric::FleschKincaid),
+            max: Some(9.0),
+            ..Lint::default()
+        };
+        let linter = Linter::new(vec![lint], true).unwrap();
+        let text = "The cat sat.\n\nIncomprehensibly convoluted institutional documentation \
+                    necessitates extraordinary interpretative perseverance.\n";
+        let matches = prose(text).lint(&linter).unwrap();
+
+        assert_eq!(1, matches.len());
+        assert_eq!(3, matches[0].line);
+        assert!(matches[0].msg.starts_with("flesch-kincaid "));
+
+        let stats = prose(text).stats();
+        assert_eq!((2, 11), (stats.sentences, stats.words));
+
+        // A section runs from its heading to the next one
+        let lint = Lint {
+            scope: Scope::Section,
+            ..linter.lints[0].lint.clone()
+        };
+        let linter = Linter::new(vec![lint], true).unwrap();
+        let text = "# Easy\n\nThe cat sat.\n\n# Hard\n\nIncomprehensibly convoluted \
+                    institutional documentation necessitates extraordinary interpretative \
+                    perseverance.\n";
+        let matches = Prose {
+            format: Format::Markdown,
+            ..prose(text)
+        }.lint(&linter)
+            .unwrap();
+
+        assert_eq!(1, matches.len());
+        assert_eq!((5, "Hard"), (matches[0].line, &matches[0].text[..]));
+    }
+
+    #[test]
+    fn prose_lint_markdown() {
+        let linter = linter(&[(r"\bvery\b", None)]);
+        let text = "Not `very` here,\n\n```\nvery\n```\n\nbut very here.\n";
+        let matches = Prose {
+            format: Format::Markdown,
+            ..prose(text)
+        }.lint(&linter)
+            .unwrap();
+
+        assert_eq!(1, matches.len());
+        assert_eq!((7, 5), (matches[0].line, matches[0].column));
+        assert_eq!(text.rfind("very").unwrap(), matches[0].offset.start);
+    }
+
+    #[test]
+    fn prose_lint_markdown_text() {
+        let linter = linter(&[(r"\bthe\s+the\b", None)]);
+        let text = "Set the `x` the value.\n";
+        let matches = Prose {
+            format: Format::Markdown,
+            ..prose(text)
+        }.lint(&linter)
+            .unwrap();
+
+        // Masked markup isn't whitespace between the words
+        assert!(matches.is_empty(), "{:?}", matches);
+
+        // The text of a match is the original, markup and all
+        let linter = self::linter(&[(r"\bin\s+order\s+to\b", None)]);
+        let text = "> We did it in order\n> to win.\n";
+        let matches = Prose {
+            format: Format::Markdown,
+            ..prose(text)
+        }.lint(&linter)
+            .unwrap();
+        assert_eq!(1, matches.len());
+        assert_eq!("in order\n> to", matches[0].text);
+    }
+
+    #[test]
+    fn prose_lint_suppressions() {
+        let linter = linter(&[(r"\bvery\b", None)]);
+        let text = "<!-- temper-disable-next-line temper.test -->\nvery\n\
+                    <!-- temper-disable-next-line temper.other -->\nvery\n\
+                    <!-- temper-disable-line -->\n";
+        let matches = Prose {
+            unused_suppressions: true,
+            ..prose(text)
+        }.lint(&linter)
+            .unwrap();
+
+        let found: Vec<_> = matches.iter().map(|m| (m.line, &m.lint[..])).collect();
+        assert_eq!(
+            vec![
+                (3, UNUSED_SUPPRESSION),
+                (4, "temper.test"),
+                (5, UNUSED_SUPPRESSION),
+            ],
+            found
+        );
+    }
+}
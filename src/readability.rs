@@ -0,0 +1,174 @@
+//! Module `readability.rs` scores how hard prose is to read, using the usual
+//! formulas over counts of its sentences, words and syllables.
+
+use std::fmt;
+
+use scope::{sentences, words};
+
+/// A readability formula.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Metric {
+    /// Flesch reading ease, where higher scores are easier to read.
+    Flesch,
+    /// Flesch-Kincaid grade level.
+    FleschKincaid,
+    /// Gunning fog index.
+    GunningFog,
+    /// Coleman-Liau index.
+    ColemanLiau,
+    /// SMOG grade.
+    Smog,
+    /// Automated readability index.
+    Ari,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 6] = [
+        Metric::Flesch,
+        Metric::FleschKincaid,
+        Metric::GunningFog,
+        Metric::ColemanLiau,
+        Metric::Smog,
+        Metric::Ari,
+    ];
+
+    /// Scores prose with the given counts. Returns `None` for prose without
+    /// any words.
+    pub fn score(self, stats: &Stats) -> Option<f64> {
+        if stats.words == 0 {
+            return None;
+        }
+
+        let words = stats.words as f64;
+        let sentences = stats.sentences.max(1) as f64;
+        let wps = words / sentences;
+        let spw = stats.syllables as f64 / words;
+
+        Some(match self {
+            Metric::Flesch => 206.835 - 1.015 * wps - 84.6 * spw,
+            Metric::FleschKincaid => 0.39 * wps + 11.8 * spw - 15.59,
+            Metric::GunningFog => 0.4 * (wps + 100.0 * stats.complex as f64 / words),
+            Metric::ColemanLiau => {
+                let l = 100.0 * stats.letters as f64 / words;
+                let s = 100.0 * sentences / words;
+                0.0588 * l - 0.296 * s - 15.8
+            }
+            Metric::Smog => 1.043 * (stats.complex as f64 * 30.0 / sentences).sqrt() + 3.1291,
+            Metric::Ari => 4.71 * stats.letters as f64 / words + 0.5 * wps - 21.43,
+        })
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Metric::Flesch => "flesch",
+            Metric::FleschKincaid => "flesch-kincaid",
+            Metric::GunningFog => "gunning-fog",
+            Metric::ColemanLiau => "coleman-liau",
+            Metric::Smog => "smog",
+            Metric::Ari => "ari",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Counts of the parts of some prose.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct Stats {
+    pub sentences: usize,
+    pub words: usize,
+    pub syllables: usize,
+    /// Words with three or more syllables.
+    pub complex: usize,
+    /// Letters and digits in words.
+    pub letters: usize,
+}
+
+impl Stats {
+    /// Counts the prose in the `paragraphs` of `text`.
+    pub fn new(text: &str, paragraphs: &[(usize, usize)]) -> Stats {
+        let mut stats = Stats::default();
+
+        for &p in paragraphs {
+            stats.sentences += sentences(text, p).len();
+
+            for (s, e) in words(&text[p.0..p.1]) {
+                let word = &text[p.0 + s..p.0 + e];
+                let n = syllables(word);
+                stats.words += 1;
+                stats.syllables += n;
+                stats.complex += (n >= 3) as usize;
+                stats.letters += word.chars().filter(|c| c.is_alphanumeric()).count();
+            }
+        }
+
+        stats
+    }
+}
+
+/// Guesses the number of syllables in an English word by counting its groups
+/// of vowels.
+pub fn syllables(word: &str) -> usize {
+    let word: Vec<char> = word.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect();
+    let vowel = |c: char| "aeiouy".contains(c);
+
+    let mut count = 0;
+    let mut prev = false;
+    for &c in &word {
+        let v = vowel(c);
+        if v && !prev {
+            count += 1;
+        }
+        prev = v;
+    }
+
+    // A final "e" is usually silent, as in "make", but not in "table"
+    let n = word.len();
+    if n > 2 && word[n - 1] == 'e' && !vowel(word[n - 2]) && word[n - 2] != 'l' {
+        count -= 1;
+    }
+
+    count.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readability_syllables() {
+        let counts: Vec<_> = ["cat", "make", "table", "readability", "queue", "the", "area"]
+            .iter()
+            .map(|w| syllables(w))
+            .collect();
+
+        assert_eq!(vec![1, 1, 2, 5, 1, 1, 2], counts);
+    }
+
+    #[test]
+    fn readability_scores() {
+        let text = "The cat sat on the mat. It was a very comfortable mat.";
+        let stats = Stats::new(text, &[(0, text.len())]);
+
+        assert_eq!(
+            Stats {
+                sentences: 2,
+                words: 12,
+                syllables: 16,
+                complex: 1,
+                letters: 41,
+            },
+            stats
+        );
+
+        let fk = Metric::FleschKincaid.score(&stats).unwrap();
+        assert!((fk - 0.39 * 6.0 - 11.8 * 16.0 / 12.0 + 15.59).abs() < 1e-9);
+        assert!(Metric::Flesch.score(&stats).unwrap() > 80.0);
+        assert_eq!(None, Metric::Ari.score(&Stats::default()));
+    }
+}
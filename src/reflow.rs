@@ -0,0 +1,83 @@
+//! Module `reflow.rs` joins the lines of each paragraph of a document, so that
+//! lints can match phrases which have been hard-wrapped across lines.
+
+/// A document with the whitespace inside each paragraph collapsed to single
+/// spaces, and paragraphs separated by a blank line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reflowed {
+    pub text: String,
+    /// The offset in the original document of each byte of `text`, followed by
+    /// the end of the last paragraph.
+    offsets: Vec<usize>,
+}
+
+impl Reflowed {
+    /// Reflows the `paragraphs` of `text`, given as sorted byte ranges.
+    pub fn new(text: &str, paragraphs: &[(usize, usize)]) -> Reflowed {
+        let mut res = String::with_capacity(text.len());
+        let mut offsets = Vec::with_capacity(text.len() + 1);
+        let mut last = 0;
+
+        for &(start, end) in paragraphs {
+            if !res.is_empty() {
+                res.push_str("\n\n");
+                offsets.push(last);
+                offsets.push(last);
+            }
+
+            let para = &text[start..end];
+            let trimmed = para.trim_start();
+            let base = start + para.len() - trimmed.len();
+            let mut space = None;
+
+            for (i, c) in trimmed.trim_end().char_indices() {
+                if c.is_whitespace() {
+                    space.get_or_insert(base + i);
+                    continue;
+                }
+
+                // A run of whitespace is replaced by a space standing in for
+                // the start of the run
+                if let Some(s) = space.take() {
+                    res.push(' ');
+                    offsets.push(s);
+                }
+                res.push(c);
+                for k in 0..c.len_utf8() {
+                    offsets.push(base + i + k);
+                }
+                last = base + i + c.len_utf8();
+            }
+        }
+        offsets.push(last);
+
+        Reflowed { text: res, offsets }
+    }
+
+    /// Maps an offset into the reflowed text back onto the original document.
+    pub fn original(&self, offset: usize) -> usize {
+        self.offsets[offset]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reflow_offsets() {
+        let text = "  in order\n  to\tgo\n\nnext  é\n";
+        let r = Reflowed::new(text, &[(0, 19), (20, 29)]);
+
+        assert_eq!("in order to go\n\nnext é", r.text);
+        assert_eq!(r.text.len() + 1, r.offsets.len());
+
+        let mat = r.text.find("order to").unwrap();
+        let (start, end) = (r.original(mat), r.original(mat + "order to".len()));
+        assert_eq!("order\n  to", &text[start..end]);
+
+        let e = r.text.find('é').unwrap();
+        assert_eq!("é", &text[r.original(e)..r.original(e + 2)]);
+        assert_eq!(text.len() - 1, r.original(r.text.len()));
+    }
+}
@@ -0,0 +1,247 @@
+//! Module `scope.rs` splits a document into the parts that a lint's `scope`
+//! refers to, such as its paragraphs or sentences.
+
+use lint::Scope;
+use markup::*;
+
+/// Abbreviations whose trailing period doesn't end a sentence.
+const ABBREVIATIONS: &[&str] = &[
+    "cf", "dr", "e.g", "eg", "i.e", "ie", "jr", "mr", "mrs", "ms", "prof", "sr", "st", "vs",
+];
+
+/// Finds the byte ranges of every part of `text` covered by `scope`, in order
+/// and without surrounding whitespace. `regions` are the regions of prose in
+/// `text`, if it's written in a markup language.
+pub fn spans(scope: Scope, text: &str, regions: Option<&[Region]>) -> Vec<(usize, usize)> {
+    let res = match scope {
+        Scope::Document => vec![(0, text.len())],
+        Scope::Paragraph => paragraphs(text, regions),
+        Scope::Sentence => paragraphs(text, regions)
+            .into_iter()
+            .flat_map(|p| sentences(text, p))
+            .collect(),
+        Scope::Heading | Scope::Admonition => match regions {
+            Some(regions) => {
+                let block = match scope {
+                    Scope::Heading => Block::Heading,
+                    _ => Block::Admonition,
+                };
+                let blocks: Vec<Region> = regions
+                    .iter()
+                    .filter(|r| r.block == block)
+                    .cloned()
+                    .collect();
+                paragraphs(text, Some(&blocks))
+            }
+            None => Vec::new(),
+        },
+        Scope::Section => {
+            let first = regions.and_then(|r| r.first()).map_or(0, |r| r.start);
+            let mut starts = vec![first];
+            starts.extend(spans(Scope::Heading, text, regions).into_iter().map(|(s, _)| s));
+            starts.dedup();
+            starts.push(text.len());
+            starts.windows(2).map(|w| (w[0], w[1])).collect()
+        }
+        Scope::Line => {
+            let mut offset = 0;
+            text.split_inclusive('\n')
+                .map(|line| {
+                    offset += line.len();
+                    (offset - line.len(), offset)
+                })
+                .collect()
+        }
+    };
+
+    res.into_iter()
+        .map(|(start, end)| trim(text, start, end))
+        .filter(|&(start, end)| start < end)
+        .collect()
+}
+
+/// Splits the paragraph of `text` spanning `start..end` into sentences.
+pub fn sentences(text: &str, (start, end): (usize, usize)) -> Vec<(usize, usize)> {
+    let para = &text[start..end];
+    let mut res = Vec::new();
+    let mut begin = 0;
+    let mut chars = para.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if !terminator(c) {
+            continue;
+        }
+
+        // Runs of terminators and any closing quotes or brackets belong to
+        // the sentence they end
+        let mut stop = i + c.len_utf8();
+        while let Some(&(j, d)) = chars.peek() {
+            if terminator(d) || "\"')]’”»".contains(d) {
+                stop = j + d.len_utf8();
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        let rest = &para[stop..];
+        let next = rest.trim_start();
+        if next.len() == rest.len() && !next.is_empty() {
+            continue;
+        }
+        if next.chars().next().is_some_and(|n| n.is_lowercase()) {
+            continue;
+        }
+        if c == '.' && abbreviation(&para[begin..i]) {
+            continue;
+        }
+
+        res.push((start + begin, start + stop));
+        begin = para.len() - next.len();
+    }
+
+    if begin < para.len() {
+        res.push((start + begin, end));
+    }
+
+    res
+}
+
+pub(crate) fn terminator(c: char) -> bool {
+    c == '.' || c == '!' || c == '?' || c == '…'
+}
+
+/// Whether `sentence` ends with an abbreviation or an initial, given
+/// everything before its final period.
+fn abbreviation(sentence: &str) -> bool {
+    let word = sentence
+        .rsplit(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or("");
+    let mut cs = word.chars();
+
+    match (cs.next(), cs.next()) {
+        (Some(c), None) => c.is_uppercase(),
+        _ => ABBREVIATIONS.contains(&&word.to_lowercase()[..]),
+    }
+}
+
+/// Finds the byte ranges of the words in `text`. Apostrophes inside of a word
+/// are part of it, and runs of digits alone aren't words.
+pub fn words(text: &str) -> Vec<(usize, usize)> {
+    let inner = |c: char| c.is_alphanumeric() || c == '\'' || c == '’';
+    let mut res = Vec::new();
+    let mut start = None;
+
+    for (i, c) in text.char_indices().chain(Some((text.len(), ' '))) {
+        match start {
+            None if c.is_alphanumeric() => start = Some(i),
+            Some(s) if !inner(c) => {
+                let word = text[s..i].trim_end_matches(['\'', '’']);
+                if word.chars().any(char::is_alphabetic) {
+                    res.push((s, s + word.len()));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    res
+}
+
+fn trim(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let s = &text[start..end];
+    let t = s.trim_start();
+    let start = start + s.len() - t.len();
+    (start, start + t.trim_end().len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(text: &str) -> Vec<&str> {
+        spans(Scope::Sentence, text, None)
+            .into_iter()
+            .map(|(s, e)| &text[s..e])
+            .collect()
+    }
+
+    #[test]
+    fn scope_sentences() {
+        assert_eq!(
+            vec![
+                "Hello there.",
+                "Mr. J. Smith said \"hi!\"",
+                "See e.g. this, i.e. that... ok?",
+                "Yes",
+            ],
+            split("Hello there. Mr. J. Smith said \"hi!\" See e.g. this, i.e. that... ok?\nYes\n")
+        );
+        assert_eq!(vec!["One.", "Two."], split("One.\n\nTwo.\n"));
+        assert_eq!(vec!["Version 1.2 is out."], split("Version 1.2 is out."));
+        assert_eq!(
+            vec!["Wait… what?", "Dr. No left…", "Fine."],
+            split("Wait… what? Dr. No left… Fine.")
+        );
+    }
+
+    #[test]
+    fn scope_words() {
+        let text = "It's the  cats' 10 10 toys";
+        let ws: Vec<_> = words(text).into_iter().map(|(s, e)| &text[s..e]).collect();
+
+        assert_eq!(vec!["It's", "the", "cats", "toys"], ws);
+    }
+
+    #[test]
+    fn scope_headings() {
+        let text = "# One\n\nText.\n\nTwo\n===\n";
+        let regions = regions(Format::Markdown, text, &Options::default()).unwrap();
+        let headings: Vec<_> = spans(Scope::Heading, text, Some(&regions))
+            .into_iter()
+            .map(|(s, e)| &text[s..e])
+            .collect();
+
+        assert_eq!(vec!["One", "Two"], headings);
+        assert!(spans(Scope::Heading, text, None).is_empty());
+    }
+
+    #[test]
+    fn scope_sections() {
+        let text = "Intro.\n\n# One\n\nText.\n\n## Two\n\nMore.\n";
+        let regions = regions(Format::Markdown, text, &Options::default()).unwrap();
+        let masked = mask(text, &regions);
+        let sections: Vec<_> = spans(Scope::Section, &masked, Some(&regions))
+            .into_iter()
+            .map(|(s, e)| &text[s..e])
+            .collect();
+
+        assert_eq!(vec!["Intro.", "One\n\nText.", "Two\n\nMore."], sections);
+        assert_eq!(vec![(0, text.len() - 1)], spans(Scope::Section, text, None));
+    }
+
+    #[test]
+    fn scope_admonitions() {
+        let text = "= Title\n\nNOTE: Be careful.\n\nText.\n";
+        let regions = regions(Format::AsciiDoc, text, &Options::default()).unwrap();
+        let notes: Vec<_> = spans(Scope::Admonition, text, Some(&regions))
+            .into_iter()
+            .map(|(s, e)| &text[s..e])
+            .collect();
+        assert_eq!(vec!["Be careful."], notes);
+    }
+
+    #[test]
+    fn scope_lines() {
+        let text = "  a b \n\nc\n";
+        let lines: Vec<_> = spans(Scope::Line, text, None)
+            .into_iter()
+            .map(|(s, e)| &text[s..e])
+            .collect();
+
+        assert_eq!(vec!["a b", "c"], lines);
+        assert_eq!(vec![(2, 9)], spans(Scope::Document, text, None));
+    }
+}
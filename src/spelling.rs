@@ -0,0 +1,466 @@
+//! Module `spelling.rs` reads Hunspell dictionaries, and uses them to check
+//! the spelling of words and to suggest corrections.
+//!
+//! Only the parts of the affix file which matter for checking single words
+//! are supported: `SET`, `FLAG`, `AF`, `TRY`, `REP`, `PFX`, `SFX`,
+//! `FORBIDDENWORD` and `NEEDAFFIX`. Compounding and morphology are ignored.
+
+use failure::Error;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// The most suggestions given for a misspelled word.
+const MAX_SUGGESTIONS: usize = 5;
+
+type Flag = u32;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum FlagMode {
+    /// Every character is a flag.
+    Char,
+    /// Every two characters are a flag.
+    Long,
+    /// Flags are comma separated numbers.
+    Num,
+}
+
+#[derive(Debug)]
+struct Affix {
+    flag: Flag,
+    /// Whether the affix can be combined with an affix of the other kind.
+    cross: bool,
+    strip: String,
+    add: String,
+    /// What the stem must look like once `add` is replaced with `strip`.
+    condition: Option<Regex>,
+}
+
+/// A Hunspell dictionary, along with any extra words that are accepted.
+#[derive(Debug)]
+pub struct Dictionary {
+    words: HashMap<String, Vec<Flag>>,
+    prefixes: Vec<Affix>,
+    suffixes: Vec<Affix>,
+    try_chars: Vec<char>,
+    replacements: Vec<(String, String)>,
+    forbidden: Option<Flag>,
+    need_affix: Option<Flag>,
+}
+
+impl Dictionary {
+    /// Reads the dictionary at `path`, which is the path to either its `.dic`
+    /// or `.aff` file, or to both without the extension.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Dictionary, Error> {
+        let path = path.as_ref();
+        let read = |ext: &str| -> Result<Vec<u8>, Error> {
+            let p = path.with_extension(ext);
+            fs::read(&p).map_err(|e| format_err!("{}: {}", p.display(), e))
+        };
+        let (aff, dic) = (read("aff")?, read("dic")?);
+
+        // Both files use the encoding given by the affix file
+        let latin1 = String::from_utf8_lossy(&aff).lines().any(|l| {
+            let mut ts = l.split_whitespace();
+            ts.next() == Some("SET")
+                && ts.next()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("ISO8859-1"))
+        });
+        let decode = |bytes: Vec<u8>| {
+            if latin1 {
+                bytes.into_iter().map(char::from).collect()
+            } else {
+                String::from_utf8_lossy(&bytes).into_owned()
+            }
+        };
+
+        Dictionary::parse(&decode(aff), &decode(dic))
+    }
+
+    /// Builds a dictionary out of the contents of its affix and dic files.
+    pub fn parse(aff: &str, dic: &str) -> Result<Dictionary, Error> {
+        let mut dict = Dictionary {
+            words: HashMap::new(),
+            prefixes: Vec::new(),
+            suffixes: Vec::new(),
+            try_chars: Vec::new(),
+            replacements: Vec::new(),
+            forbidden: None,
+            need_affix: None,
+        };
+        let mut mode = FlagMode::Char;
+        let mut aliases: Vec<Vec<Flag>> = Vec::new();
+        // Whether each affix flag seen so far can be cross combined
+        let mut headers: HashMap<(bool, Flag), bool> = HashMap::new();
+
+        for line in aff.lines() {
+            let ts: Vec<&str> = line.split_whitespace().collect();
+            match ts.first().cloned() {
+                Some("FLAG") if ts.len() > 1 => {
+                    mode = match ts[1] {
+                        "long" => FlagMode::Long,
+                        "num" => FlagMode::Num,
+                        _ => FlagMode::Char,
+                    }
+                }
+                Some("TRY") if ts.len() > 1 => dict.try_chars = ts[1].chars().collect(),
+                Some("REP") if ts.len() > 2 => {
+                    dict.replacements
+                        .push((ts[1].replace('_', " "), ts[2].replace('_', " ")));
+                }
+                Some("AF") if ts.len() > 1 && ts[1].parse::<usize>().is_err() => {
+                    aliases.push(flags(ts[1], mode, &[]));
+                }
+                Some("FORBIDDENWORD") if ts.len() > 1 => {
+                    dict.forbidden = flags(ts[1], mode, &[]).first().cloned();
+                }
+                Some("NEEDAFFIX") if ts.len() > 1 => {
+                    dict.need_affix = flags(ts[1], mode, &[]).first().cloned();
+                }
+                Some(kind @ "PFX") | Some(kind @ "SFX") if ts.len() >= 4 => {
+                    let prefix = kind == "PFX";
+                    let flag = match flags(ts[1], mode, &[]).first() {
+                        Some(&f) => f,
+                        None => continue,
+                    };
+
+                    let cross = match headers.get(&(prefix, flag)) {
+                        Some(&cross) => cross,
+                        None => {
+                            headers.insert((prefix, flag), ts[2] == "Y");
+                            continue;
+                        }
+                    };
+
+                    let strip = if ts[2] == "0" { "" } else { ts[2] };
+                    let add = ts[3].split('/').next().unwrap_or("");
+                    let add = if add == "0" { "" } else { add };
+                    let condition = match ts.get(4) {
+                        Some(&c) if c != "." => Some(Regex::new(&if prefix {
+                            format!("^(?:{})", c)
+                        } else {
+                            format!("(?:{})$", c)
+                        })?),
+                        _ => None,
+                    };
+
+                    let affix = Affix {
+                        flag,
+                        cross,
+                        strip: String::from(strip),
+                        add: String::from(add),
+                        condition,
+                    };
+                    if prefix {
+                        dict.prefixes.push(affix);
+                    } else {
+                        dict.suffixes.push(affix);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if dict.try_chars.is_empty() {
+            dict.try_chars = "esianrtolcdugmphbyfvkwzESIANRTOLCDUGMPHBYFVKWZ'".chars().collect();
+        }
+
+        // The first line of the dic file is the number of words in it
+        for line in dic.lines().skip(1) {
+            let entry = match line.split(['\t', ' ']).next() {
+                Some(e) if !e.is_empty() => e,
+                _ => continue,
+            };
+            let (word, fs) = match entry.find('/') {
+                Some(i) => (&entry[..i], flags(&entry[i + 1..], mode, &aliases)),
+                None => (entry, Vec::new()),
+            };
+            dict.words
+                .entry(String::from(word))
+                .or_default()
+                .extend(fs);
+        }
+
+        Ok(dict)
+    }
+
+    /// Accepts `word` as correctly spelled.
+    pub fn add(&mut self, word: &str) {
+        self.words.entry(String::from(word)).or_default();
+    }
+
+    /// Accepts every word listed in the file at `path`, which has one word per
+    /// line. Blank lines and lines starting with `#` are skipped.
+    pub fn accept<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let list =
+            fs::read_to_string(path).map_err(|e| format_err!("{}: {}", path.display(), e))?;
+
+        for word in list.lines().map(str::trim) {
+            if !word.is_empty() && !word.starts_with('#') {
+                self.add(word);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether `word` is spelled correctly. Capitalized and upper case forms of
+    /// known words are also accepted, and so are their possessives, which
+    /// dictionaries often don't list and added words never have.
+    pub fn check(&self, word: &str) -> bool {
+        let word = word.replace('’', "'");
+        self.cased(&word)
+            || ["'s", "'S"]
+                .iter()
+                .filter_map(|s| word.strip_suffix(s))
+                .any(|stem| self.cased(stem))
+    }
+
+    /// Whether `word`, or a form of it in another case, is known.
+    fn cased(&self, word: &str) -> bool {
+        if self.known(word) {
+            return true;
+        }
+
+        let mut cs = word.chars();
+        let first = match cs.next() {
+            Some(c) => c,
+            None => return true,
+        };
+        let rest = cs.as_str();
+        let lower = word.to_lowercase();
+
+        if first.is_uppercase() && !rest.chars().any(char::is_uppercase) {
+            self.known(&lower)
+        } else if word.chars().all(|c| !c.is_lowercase()) {
+            let capitalized: String = first
+                .to_uppercase()
+                .chain(rest.to_lowercase().chars())
+                .collect();
+            self.known(&lower) || self.known(&capitalized)
+        } else {
+            false
+        }
+    }
+
+    /// Suggests correctly spelled words that `word` may have been meant to be,
+    /// best first.
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        let mut candidates: Vec<String> = Vec::new();
+
+        for (from, to) in &self.replacements {
+            for (i, _) in word.match_indices(&from[..]) {
+                candidates.push(format!("{}{}{}", &word[..i], to, &word[i + from.len()..]));
+            }
+        }
+
+        let join = |cs: &[char]| cs.iter().collect::<String>();
+        for i in 0..chars.len() {
+            let mut cs = chars.clone();
+            if i + 1 < chars.len() {
+                cs.swap(i, i + 1);
+                candidates.push(join(&cs));
+                cs.swap(i, i + 1);
+            }
+            for &t in &self.try_chars {
+                if t != chars[i] {
+                    cs[i] = t;
+                    candidates.push(join(&cs));
+                }
+            }
+            let mut cs = chars.clone();
+            cs.remove(i);
+            candidates.push(join(&cs));
+        }
+        for i in 0..=chars.len() {
+            for &t in &self.try_chars {
+                let mut cs = chars.clone();
+                cs.insert(i, t);
+                candidates.push(join(&cs));
+            }
+        }
+        for i in 1..chars.len() {
+            candidates.push(format!("{} {}", join(&chars[..i]), join(&chars[i..])));
+        }
+
+        let mut seen = HashSet::new();
+        candidates
+            .into_iter()
+            .filter(|c| c.split(' ').all(|w| self.check(w)) && seen.insert(c.clone()))
+            .take(MAX_SUGGESTIONS)
+            .collect()
+    }
+
+    /// Whether `word` is in the dictionary as is, or as a stem with affixes
+    /// that it allows.
+    fn known(&self, word: &str) -> bool {
+        if let Some(fs) = self.words.get(word) {
+            if !self.has(fs, self.forbidden) && !self.has(fs, self.need_affix) {
+                return true;
+            }
+        }
+
+        let allows = |stem: &str, flag: Flag, other: Option<Flag>| {
+            self.words.get(stem).is_some_and(|fs| {
+                fs.contains(&flag) && other.is_none_or(|o| fs.contains(&o))
+                    && !self.has(fs, self.forbidden)
+            })
+        };
+
+        for sfx in &self.suffixes {
+            let stem = match strip_suffix(word, sfx) {
+                Some(stem) => stem,
+                None => continue,
+            };
+            if allows(&stem, sfx.flag, None) {
+                return true;
+            }
+
+            if sfx.cross {
+                for pfx in self.prefixes.iter().filter(|p| p.cross) {
+                    if let Some(root) = strip_prefix(&stem, pfx) {
+                        if allows(&root, sfx.flag, Some(pfx.flag)) {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.prefixes.iter().any(|pfx| {
+            strip_prefix(word, pfx).is_some_and(|stem| allows(&stem, pfx.flag, None))
+        })
+    }
+
+    fn has(&self, fs: &[Flag], flag: Option<Flag>) -> bool {
+        flag.is_some_and(|f| fs.contains(&f))
+    }
+}
+
+/// Undoes `sfx` on `word`, if `word` could have been made with it.
+fn strip_suffix(word: &str, sfx: &Affix) -> Option<String> {
+    let base = word.strip_suffix(&sfx.add[..])?;
+    if base.is_empty() {
+        return None;
+    }
+
+    let stem = format!("{}{}", base, sfx.strip);
+    match sfx.condition {
+        Some(ref c) if !c.is_match(&stem) => None,
+        _ => Some(stem),
+    }
+}
+
+/// Undoes `pfx` on `word`, if `word` could have been made with it.
+fn strip_prefix(word: &str, pfx: &Affix) -> Option<String> {
+    let base = word.strip_prefix(&pfx.add[..])?;
+    if base.is_empty() {
+        return None;
+    }
+
+    let stem = format!("{}{}", pfx.strip, base);
+    match pfx.condition {
+        Some(ref c) if !c.is_match(&stem) => None,
+        _ => Some(stem),
+    }
+}
+
+/// Parses the flags in `s`, resolving them through `aliases` if the affix
+/// file defined any.
+fn flags(s: &str, mode: FlagMode, aliases: &[Vec<Flag>]) -> Vec<Flag> {
+    if !aliases.is_empty() {
+        if let Ok(n) = s.parse::<usize>() {
+            return aliases.get(n.wrapping_sub(1)).cloned().unwrap_or_default();
+        }
+    }
+
+    match mode {
+        FlagMode::Char => s.chars().map(|c| c as Flag).collect(),
+        FlagMode::Long => s.chars()
+            .collect::<Vec<_>>()
+            .chunks(2)
+            .map(|p| p.iter().fold(0, |f, &c| (f << 16) | c as Flag))
+            .collect(),
+        FlagMode::Num => s.split(',').filter_map(|n| n.trim().parse().ok()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "\
+SET UTF-8
+TRY esiantrolcdugmph
+REP 1
+REP alot a_lot
+
+PFX U Y 1
+PFX U 0 un .
+
+SFX S Y 2
+SFX S y ies [^aeiou]y
+SFX S 0 s [^y]
+
+SFX M N 1
+SFX M 0 's .
+";
+
+    const DIC: &str = "\
+6
+happy/U
+party/SM
+cat/SM
+Paris/M
+lot
+a
+";
+
+    fn dict() -> Dictionary {
+        Dictionary::parse(AFF, DIC).unwrap()
+    }
+
+    #[test]
+    fn spelling_check() {
+        let d = dict();
+
+        for word in &["happy", "unhappy", "parties", "cats", "cat's", "Cats", "CATS", "Paris's"] {
+            assert!(d.check(word), "{}", word);
+        }
+        for word in &["partys", "unparty", "paris", "cAts", "happys"] {
+            assert!(!d.check(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn spelling_suggest() {
+        let d = dict();
+
+        assert_eq!(vec![String::from("cat")], d.suggest("cta"));
+        assert_eq!("a lot", d.suggest("alot")[0]);
+        assert!(d.suggest("xyzzy").is_empty());
+    }
+
+    #[test]
+    fn spelling_accept() {
+        let mut d = dict();
+        assert!(!d.check("temper"));
+        d.add("temper");
+        assert!(d.check("temper"));
+        assert!(d.check("Temper"));
+        assert!(d.check("temper's"));
+        assert!(d.check("TEMPER’S"));
+    }
+
+    #[test]
+    fn spelling_long_flags() {
+        let aff = "FLAG long\nSFX Aa Y 1\nSFX Aa 0 ing .\n";
+        let d = Dictionary::parse(aff, "1\ntest/AaBb\n").unwrap();
+
+        assert!(d.check("testing"));
+        assert!(!d.check("tests"));
+    }
+}
@@ -0,0 +1,152 @@
+//! Module `suppress.rs` finds the inline directives which turn lints off for
+//! part of a document, such as `<!-- temper-disable-next-line temper.cliches -->`.
+//!
+//! Directives are recognized anywhere in the text, so they can be written in
+//! whatever comment syntax the document uses (or as plain text):
+//!
+//! - `temper-disable-next-line [LINT...]` suppresses the line after it
+//! - `temper-disable-line [LINT...]` suppresses its own line
+//! - `temper-disable [LINT...]` suppresses everything up to a matching
+//!   `temper-enable [LINT...]`, or the end of the document
+//!
+//! Without any lint names, a directive applies to every lint. A name also
+//! covers every lint below it, so `temper` covers `temper.cliches`.
+
+use regex::Regex;
+
+/// The name of the lint used to report directives which don't suppress
+/// anything.
+pub const UNUSED_SUPPRESSION: &str = "temper.unused-suppression";
+
+lazy_static! {
+    static ref DIRECTIVE: Regex = Regex::new(
+        r"temper-(disable-next-line|disable-line|disable|enable)\b((?:[ \t,]+\w[\w.\-]*)*)"
+    ).unwrap();
+}
+
+/// A range of a document in which some lints are turned off.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Suppression {
+    /// The byte offsets of the directive itself.
+    pub directive: (usize, usize),
+    /// The byte range that is suppressed.
+    pub start: usize,
+    pub end: usize,
+    /// The lints which are suppressed; all of them if empty.
+    pub lints: Vec<String>,
+}
+
+impl Suppression {
+    /// Whether this suppression covers a match of `lint` starting at `offset`.
+    pub fn covers(&self, lint: &str, offset: usize) -> bool {
+        offset >= self.start && offset < self.end
+            && (self.lints.is_empty() || self.lints.iter().any(|l| {
+                lint == l || (lint.starts_with(&l[..]) && lint[l.len()..].starts_with('.'))
+            }))
+    }
+}
+
+/// Finds every suppression in `text`. `clens` holds the starting offset of each
+/// line, as given by `Prose::line_lengths`.
+pub fn suppressions(text: &str, clens: &[usize]) -> Vec<Suppression> {
+    let mut res = Vec::new();
+    let mut open: Vec<Suppression> = Vec::new();
+
+    let line_of = |offset: usize| match clens.binary_search(&offset) {
+        Ok(i) => i,
+        Err(i) => i - 1,
+    };
+    let line_start = |line: usize| *clens.get(line).unwrap_or(&text.len());
+
+    for caps in DIRECTIVE.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        let lints: Vec<String> = caps[2]
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(|l| l.trim_end_matches('-'))
+            .filter(|l| !l.is_empty())
+            .map(String::from)
+            .collect();
+        let line = line_of(whole.start());
+        let directive = (whole.start(), whole.end());
+
+        match &caps[1] {
+            "disable-next-line" => res.push(Suppression {
+                directive,
+                start: line_start(line + 1),
+                end: line_start(line + 2),
+                lints,
+            }),
+            "disable-line" => res.push(Suppression {
+                directive,
+                start: line_start(line),
+                end: line_start(line + 1),
+                lints,
+            }),
+            "disable" => open.push(Suppression {
+                directive,
+                start: whole.end(),
+                end: text.len(),
+                lints,
+            }),
+            _ => {
+                let (closed, still): (Vec<_>, Vec<_>) = open.into_iter().partition(|s| {
+                    lints.is_empty()
+                        || (!s.lints.is_empty() && s.lints.iter().all(|l| lints.contains(l)))
+                });
+                open = still;
+                res.extend(closed.into_iter().map(|s| Suppression {
+                    end: whole.start(),
+                    ..s
+                }));
+            }
+        }
+    }
+
+    res.extend(open);
+    res.sort_by_key(|s| s.directive);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clens(text: &str) -> Vec<usize> {
+        let mut res = vec![0];
+        res.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        res
+    }
+
+    #[test]
+    fn suppress_next_line() {
+        let text = "a\n<!-- temper-disable-next-line temper.cliches -->\nb\nc\n";
+        let ss = suppressions(text, &clens(text));
+
+        assert_eq!(1, ss.len());
+        assert_eq!(&text[ss[0].start..ss[0].end], "b\n");
+        assert_eq!(vec![String::from("temper.cliches")], ss[0].lints);
+    }
+
+    #[test]
+    fn suppress_range() {
+        let text = "a\n# temper-disable temper.jargon, temper.x\nb\n# temper-enable\nc\n";
+        let ss = suppressions(text, &clens(text));
+        let (b, c) = (text.find("\nb").unwrap() + 1, text.find("\nc").unwrap() + 1);
+
+        assert_eq!(1, ss.len());
+        assert!(ss[0].covers("temper.jargon", b));
+        assert!(ss[0].covers("temper.x.y", b));
+        assert!(!ss[0].covers("temper.jargonish", b));
+        assert!(!ss[0].covers("temper.jargon", c));
+    }
+
+    #[test]
+    fn suppress_unclosed() {
+        let text = "temper-disable\nb\n";
+        let ss = suppressions(text, &clens(text));
+
+        assert_eq!(text.len(), ss[0].end);
+        assert!(ss[0].lints.is_empty());
+        assert!(ss[0].covers("anything", text.len() - 2));
+    }
+}
@@ -22,21 +22,21 @@ pub fn lines(buf: &[u8], eol: u8) -> usize {
 mod tests {
     use super::*;
 
-    const LF: &'static str =
+    const LF: &str =
         "\
          This is some wonderful multi-line text to test to see if `lines` can \n\
          correctly determine the number of lines in a string. This str uses LF \n\
          line endings.";
 
-    const CRLF: &'static str =
+    const CRLF: &str =
         "\
          This is some wonderful multi-line text to test to see if `lines` can \r\n\
          correctly determine the number of lines in a string. This str uses \r\n\
          CRLF line endings.";
 
-    const LF_EMPTY: &'static str = "\n\n";
+    const LF_EMPTY: &str = "\n\n";
 
-    const CRLF_EMPTY: &'static str = "\r\n\r\n";
+    const CRLF_EMPTY: &str = "\r\n\r\n";
 
     // TODO: Quickcheck?
     #[test]
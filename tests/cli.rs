@@ -0,0 +1,157 @@
+//! Runs the `temper` binary end to end, for the behavior that lives outside of
+//! the library: reading input, exit statuses and output.
+
+extern crate serde_json;
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{self, Command, Output, Stdio};
+
+const VERY: &str = "\
+[lint]
+name = 'test.very'
+tokens = ['very']
+";
+
+/// Creates an empty directory for a test to run in.
+fn scratch(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("temper-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Runs temper in `dir` with `args`, feeding it `stdin`.
+fn temper(dir: &PathBuf, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_temper"))
+        .current_dir(dir)
+        .arg("--no-config")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Temper may exit without reading its input, when its arguments are bad
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn cli_stdin() {
+    let dir = scratch("stdin");
+    fs::write(dir.join("very.toml"), VERY).unwrap();
+
+    let output = temper(&dir, &["-l", "very.toml"], "A very good\nvery day.\n");
+    assert!(output.status.success());
+    let out = stdout(&output);
+    assert!(out.contains("<stdin>:1:3 test.very"), "{}", out);
+    assert!(out.contains("<stdin>:2:1 test.very"), "{}", out);
+    assert!(out.contains("2 suggestions found."), "{}", out);
+
+    let args = ["-l", "very.toml", "--stdin-filename", "notes.md"];
+    let output = temper(&dir, &args, "`very` is very.\n");
+    let out = stdout(&output);
+    assert!(out.contains("notes.md:1:11 test.very"), "{}", out);
+    assert!(out.contains("1 suggestions found."), "{}", out);
+}
+
+#[test]
+fn cli_json_path() {
+    let dir = scratch("json-path");
+    fs::write(dir.join("very.toml"), VERY).unwrap();
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("a.md"), "very\n").unwrap();
+    fs::write(dir.join("sub").join("a.md"), "very\n").unwrap();
+
+    let output = temper(&dir, &["-l", "very.toml", "-o", "json", "a.md", "sub/a.md"], "");
+    let mut files: Vec<String> = stdout(&output)
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+        .map(|v| v["file"].as_str().unwrap().to_string())
+        .collect();
+    files.sort();
+    assert_eq!(vec!["a.md", "sub/a.md"], files);
+}
+
+#[test]
+fn cli_missing_paths() {
+    let dir = scratch("missing");
+    fs::write(dir.join("very.toml"), VERY).unwrap();
+    fs::write(dir.join("a.md"), "very\n").unwrap();
+
+    let output = temper(&dir, &["-l", "nonexist.toml", "--fail-on", "info", "a.md"], "");
+    assert_eq!(Some(2), output.status.code());
+
+    let output = temper(&dir, &["-l", "very.toml", "--fail-on", "info", "missing.md"], "");
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing.md"));
+
+    let output = temper(&dir, &["-l", "very.toml", "--fail-on", "info", "a.md"], "");
+    assert_eq!(Some(1), output.status.code());
+}
+
+#[test]
+fn cli_invalid_values() {
+    let dir = scratch("invalid");
+    fs::write(dir.join("very.toml"), VERY).unwrap();
+
+    for args in &[
+        ["-l", "very.toml", "--split", "abc"],
+        ["-l", "very.toml", "--split-overlap", "-"],
+        ["-l", "very.toml", "--format", "troff"],
+    ] {
+        let output = temper(&dir, args, "very\n");
+        assert_eq!(Some(2), output.status.code(), "{:?}", args);
+    }
+}
+
+#[test]
+fn cli_fix_dry_run() {
+    let dir = scratch("dry-run");
+    fs::write(
+        dir.join("util.toml"),
+        "[lint]\nname = 'test.util'\nseverity = 'error'\n\n[mapping]\nutilize = 'use'\n",
+    ).unwrap();
+    fs::write(dir.join("a.md"), "We utilize it.\n").unwrap();
+
+    // Nothing is written, so the findings still count
+    let args = ["-l", "util.toml", "--fix-dry-run", "--fail-on", "error", "a.md"];
+    let output = temper(&dir, &args, "");
+    let out = stdout(&output);
+    assert_eq!(Some(1), output.status.code());
+    assert!(out.contains("-We utilize it.\n+We use it.\n"), "{}", out);
+    assert!(out.contains("1 suggestions found."), "{}", out);
+    assert!(out.contains("1 fixes available."), "{}", out);
+    assert_eq!("We utilize it.\n", fs::read_to_string(dir.join("a.md")).unwrap());
+
+    let output = temper(&dir, &["-l", "util.toml", "--fix", "--fail-on", "error", "a.md"], "");
+    let out = stdout(&output);
+    assert!(output.status.success());
+    assert!(out.contains("0 suggestions found."), "{}", out);
+    assert_eq!("We use it.\n", fs::read_to_string(dir.join("a.md")).unwrap());
+}
+
+#[test]
+fn cli_verbose_lines() {
+    let dir = scratch("verbose");
+    fs::write(
+        dir.join("length.toml"),
+        "[lint]\nname = 'test.length'\nkind = 'length'\nmax_sentences = 1\n",
+    ).unwrap();
+
+    let text = "Intro.\n\nOne is here.\nTwo is here.\nThree is here.\n";
+    let output = temper(&dir, &["-l", "length.toml", "-o", "verbose"], text);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(out.contains(" --> <stdin>:3:1\n"), "{}", out);
+    assert!(out.contains("3 | One is here. \n"), "{}", out);
+    assert!(out.contains("4 | Two is here. \n"), "{}", out);
+    assert!(out.contains("5 | Three is here.\n"), "{}", out);
+}